@@ -4,10 +4,13 @@
 //! local ONNX embedding model and tokenizer files instead of using the
 //! pre-built FastEmbed models.
 
+// Imports used by the commented-out examples below
+#[allow(unused_imports)]
 use localsearch::{
     DocumentIndexer, DocumentRequest, LocalEmbedder, LocalSearch, SearchType,
     SqliteLocalSearchEngine,
 };
+#[allow(unused_imports)]
 use std::path::PathBuf;
 
 fn main() -> anyhow::Result<()> {
@@ -73,6 +76,7 @@ fn main() -> anyhow::Result<()> {
         path: "example/doc1".to_string(),
         content: "This is an example document with custom embeddings.".to_string(),
         metadata: None,
+        embedding: None,
     })?;
 
     // Search using the custom model
@@ -0,0 +1,450 @@
+//! Async API for use inside tokio services.
+//!
+//! Embedding inference and SQLite calls block the calling thread. [`AsyncEngine`] wraps
+//! a thread-safe engine such as [`crate::SharedSqliteEngine`] and runs every call on
+//! tokio's blocking thread pool, so async handlers never stall the runtime. Document
+//! listings can be consumed page by page as a [`ResultStream`]; searches return a
+//! `Vec`, as they are already bounded by [`SearchOptions::limit`].
+//!
+//! ```rust,no_run
+//! use localsearch::asynchronous::{AsyncEngine, AsyncLocalSearch};
+//! use localsearch::{SearchType, SqliteLocalSearchEngine};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let engine = SqliteLocalSearchEngine::new("index.db", None)?.into_shared()?;
+//! let engine = AsyncEngine::new(engine);
+//! let results = engine.search("example", SearchType::FullText, Some(10), None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{LocalSearchError, Result};
+use crate::traits::{
+    DocumentIndexer, DocumentInfo, DocumentRequest, IndexStats, ListOptions, LocalSearch,
+    QueryExplanation, SearchOptions, SearchOutcome, SearchResult, SearchType, StoredDocument,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Number of items buffered in a [`ResultStream`] before the producer waits.
+const STREAM_BUFFER: usize = 64;
+/// Number of documents fetched per query when streaming a document listing.
+const LIST_PAGE_SIZE: usize = 256;
+
+/// Async counterpart of [`DocumentIndexer`].
+///
+/// `refresh` is not offered because it needs exclusive access to the engine; call it
+/// on the engine before wrapping it.
+pub trait AsyncDocumentIndexer {
+    fn insert_document(&self, request: DocumentRequest) -> impl Future<Output = Result<()>> + Send;
+    fn upsert_document(&self, request: DocumentRequest) -> impl Future<Output = Result<()>> + Send;
+    fn delete_document(&self, path: &str) -> impl Future<Output = Result<()>> + Send;
+    fn get_document(
+        &self,
+        path: &str,
+    ) -> impl Future<Output = Result<Option<StoredDocument>>> + Send;
+    /// Lists documents ordered by path.
+    fn list_documents(
+        &self,
+        options: ListOptions,
+    ) -> impl Future<Output = Result<Vec<DocumentInfo>>> + Send;
+    /// Streams documents ordered by path, fetching them page by page.
+    fn list_documents_stream(&self, options: ListOptions) -> ResultStream<DocumentInfo>;
+    /// Deletes all documents whose path starts with `prefix` and returns how many.
+    fn delete_prefix(&self, prefix: &str) -> impl Future<Output = Result<usize>> + Send;
+    fn stats(&self) -> impl Future<Output = Result<IndexStats>> + Send;
+    /// Upserts a batch of documents in one write, see [`DocumentIndexer::upsert_batch`].
+    fn upsert_batch(
+        &self,
+        requests: Vec<DocumentRequest>,
+        embeddings: Option<Vec<Vec<f32>>>,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Async counterpart of [`LocalSearch`].
+pub trait AsyncLocalSearch {
+    fn search(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        top: Option<i8>,
+        path_filters: Option<&[String]>,
+    ) -> impl Future<Output = Result<Vec<SearchResult>>> + Send;
+
+    fn search_with_options(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<Vec<SearchResult>>> + Send;
+
+    /// Searches and returns the suggestion and query explanations too, see
+    /// [`LocalSearch::search_with_details`].
+    fn search_with_details(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<SearchOutcome>> + Send;
+
+    /// Searches by a precomputed embedding, see [`LocalSearch::search_by_vector`].
+    fn search_by_vector(
+        &self,
+        embedding: &[f32],
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<Vec<SearchResult>>> + Send;
+
+    /// Finds similar documents, see [`LocalSearch::find_similar`].
+    fn find_similar(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<Vec<SearchResult>>> + Send;
+
+    /// Suggests a corrected query, see [`LocalSearch::suggest`].
+    fn suggest(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// Shows how a query is rewritten, see [`LocalSearch::explain_query`].
+    fn explain_query(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> impl Future<Output = Result<Vec<QueryExplanation>>> + Send;
+}
+
+/// Runs a thread-safe engine on tokio's blocking thread pool.
+///
+/// Must be used from within a tokio runtime.
+pub struct AsyncEngine<E> {
+    engine: Arc<E>,
+}
+
+impl<E> Clone for AsyncEngine<E> {
+    fn clone(&self) -> Self {
+        AsyncEngine {
+            engine: Arc::clone(&self.engine),
+        }
+    }
+}
+
+impl<E: Send + Sync + 'static> AsyncEngine<E> {
+    pub fn new(engine: E) -> Self {
+        AsyncEngine {
+            engine: Arc::new(engine),
+        }
+    }
+
+    /// Returns the wrapped engine for blocking calls.
+    pub fn engine(&self) -> &E {
+        &self.engine
+    }
+
+    /// Runs `f` with the engine on the blocking thread pool.
+    async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&E) -> Result<T> + Send + 'static,
+    {
+        let engine = Arc::clone(&self.engine);
+        tokio::task::spawn_blocking(move || f(&engine))
+            .await
+            .map_err(|e| LocalSearchError::Panicked(format!("Blocking task failed: {}", e)))?
+    }
+
+    /// Runs `produce` with the engine on the blocking thread pool, streaming what it
+    /// sends. `produce` should stop once sending fails, as the stream was dropped.
+    fn stream<T, F>(&self, produce: F) -> ResultStream<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&E, &mpsc::Sender<Result<T>>) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let engine = Arc::clone(&self.engine);
+        tokio::task::spawn_blocking(move || produce(&engine, &sender));
+        ResultStream { receiver }
+    }
+}
+
+impl<E: DocumentIndexer + Send + Sync + 'static> AsyncDocumentIndexer for AsyncEngine<E> {
+    async fn insert_document(&self, request: DocumentRequest) -> Result<()> {
+        self.run(move |engine| engine.insert_document(request))
+            .await
+    }
+
+    async fn upsert_document(&self, request: DocumentRequest) -> Result<()> {
+        self.run(move |engine| engine.upsert_document(request))
+            .await
+    }
+
+    async fn delete_document(&self, path: &str) -> Result<()> {
+        let path = path.to_string();
+        self.run(move |engine| engine.delete_document(&path)).await
+    }
+
+    async fn get_document(&self, path: &str) -> Result<Option<StoredDocument>> {
+        let path = path.to_string();
+        self.run(move |engine| engine.get_document(&path)).await
+    }
+
+    async fn list_documents(&self, options: ListOptions) -> Result<Vec<DocumentInfo>> {
+        self.run(move |engine| engine.list_documents(&options))
+            .await
+    }
+
+    fn list_documents_stream(&self, options: ListOptions) -> ResultStream<DocumentInfo> {
+        self.stream(move |engine, sender| {
+            let mut page = options;
+            let mut remaining = page.limit;
+            loop {
+                let page_size = remaining.map_or(LIST_PAGE_SIZE, |r| r.min(LIST_PAGE_SIZE));
+                if page_size == 0 {
+                    return;
+                }
+                page.limit = Some(page_size);
+                let documents = match engine.list_documents(&page) {
+                    Ok(documents) => documents,
+                    Err(e) => {
+                        let _ = sender.blocking_send(Err(e));
+                        return;
+                    }
+                };
+                let fetched = documents.len();
+                for document in documents {
+                    if sender.blocking_send(Ok(document)).is_err() {
+                        return;
+                    }
+                }
+                if fetched < page_size {
+                    return;
+                }
+                page.offset += fetched;
+                remaining = remaining.map(|r| r - fetched);
+            }
+        })
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<usize> {
+        let prefix = prefix.to_string();
+        self.run(move |engine| engine.delete_prefix(&prefix)).await
+    }
+
+    async fn stats(&self) -> Result<IndexStats> {
+        self.run(|engine| engine.stats()).await
+    }
+
+    async fn upsert_batch(
+        &self,
+        requests: Vec<DocumentRequest>,
+        embeddings: Option<Vec<Vec<f32>>>,
+    ) -> Result<()> {
+        self.run(move |engine| engine.upsert_batch(requests, embeddings))
+            .await
+    }
+}
+
+impl<E: LocalSearch + Send + Sync + 'static> AsyncLocalSearch for AsyncEngine<E> {
+    async fn search(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        top: Option<i8>,
+        path_filters: Option<&[String]>,
+    ) -> Result<Vec<SearchResult>> {
+        let query = query.to_string();
+        let path_filters = path_filters.map(|filters| filters.to_vec());
+        self.run(move |engine| engine.search(&query, search_type, top, path_filters.as_deref()))
+            .await
+    }
+
+    async fn search_with_options(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let query = query.to_string();
+        let options = options.clone();
+        self.run(move |engine| engine.search_with_options(&query, search_type, &options))
+            .await
+    }
+
+    async fn search_with_details(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        let query = query.to_string();
+        let options = options.clone();
+        self.run(move |engine| engine.search_with_details(&query, search_type, &options))
+            .await
+    }
+
+    async fn search_by_vector(
+        &self,
+        embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let embedding = embedding.to_vec();
+        let options = options.clone();
+        self.run(move |engine| engine.search_by_vector(&embedding, &options))
+            .await
+    }
+
+    async fn find_similar(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let path = path.to_string();
+        let options = options.clone();
+        self.run(move |engine| engine.find_similar(&path, terms, &options))
+            .await
+    }
+
+    async fn suggest(&self, query: &str, options: &SearchOptions) -> Result<Option<String>> {
+        let query = query.to_string();
+        let options = options.clone();
+        self.run(move |engine| engine.suggest(&query, &options))
+            .await
+    }
+
+    async fn explain_query(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<QueryExplanation>> {
+        let query = query.to_string();
+        let options = options.clone();
+        self.run(move |engine| engine.explain_query(&query, &options))
+            .await
+    }
+}
+
+/// A stream of results produced on the blocking thread pool.
+///
+/// Implements [`futures_core::Stream`]; [`ResultStream::next`] is provided for use
+/// without a stream combinator library. An error ends the stream. Dropping the stream
+/// stops the producer.
+pub struct ResultStream<T> {
+    receiver: mpsc::Receiver<Result<T>>,
+}
+
+impl<T> ResultStream<T> {
+    /// Waits for the next result, or `None` once the stream is finished.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        self.receiver.recv().await
+    }
+
+    /// Collects the remaining results, stopping at the first error.
+    pub async fn try_collect(mut self) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        while let Some(item) = self.next().await {
+            items.push(item?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T> futures_core::Stream for ResultStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SqliteLocalSearchEngine;
+    use tempfile::TempDir;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+    }
+
+    fn create_async_engine() -> (AsyncEngine<crate::SharedSqliteEngine>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        (AsyncEngine::new(engine.into_shared().unwrap()), temp_dir)
+    }
+
+    fn documents(count: usize) -> Vec<DocumentRequest> {
+        (0..count)
+            .map(|i| DocumentRequest {
+                path: format!("docs/{:04}.md", i),
+                content: format!("async document number {}", i),
+                metadata: None,
+                embedding: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_async_index_and_search() {
+        let (engine, _temp_dir) = create_async_engine();
+        runtime().block_on(async {
+            engine.upsert_batch(documents(3), None).await.unwrap();
+            engine.delete_document("docs/0001.md").await.unwrap();
+
+            let results = engine
+                .search("async", SearchType::FullText, Some(10), None)
+                .await
+                .unwrap();
+            assert_eq!(results.len(), 2);
+            assert!(engine.get_document("docs/0000.md").await.unwrap().is_some());
+            assert_eq!(engine.stats().await.unwrap().document_count, 2);
+        });
+    }
+
+    #[test]
+    fn test_list_documents_stream_pages_through_results() {
+        let (engine, _temp_dir) = create_async_engine();
+        runtime().block_on(async {
+            engine
+                .upsert_batch(documents(LIST_PAGE_SIZE * 2 + 10), None)
+                .await
+                .unwrap();
+
+            let all = engine
+                .list_documents_stream(ListOptions::default())
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(all.len(), LIST_PAGE_SIZE * 2 + 10);
+            assert!(all.windows(2).all(|pair| pair[0].path < pair[1].path));
+
+            let options = ListOptions {
+                limit: Some(LIST_PAGE_SIZE + 5),
+                offset: 3,
+                ..ListOptions::default()
+            };
+            let limited = engine
+                .list_documents_stream(options)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(limited.len(), LIST_PAGE_SIZE + 5);
+            assert_eq!(limited[0].path, "docs/0003.md");
+
+            // Dropping a stream early stops the producer without errors
+            let mut stream = engine.list_documents_stream(ListOptions::default());
+            assert!(stream.next().await.unwrap().is_ok());
+            drop(stream);
+        });
+    }
+}
@@ -1,13 +1,22 @@
 mod util;
 
 use clap::{Parser, Subcommand};
+use localsearch::export::{ExportFormat, ExportOptions};
+use localsearch::ingest::{
+    CsvColumnMapping, CsvExtractor, IngestObserver, Ingestor, JsonExtractor, NoopObserver,
+    PipelineConfig, TextExtractor,
+};
+use localsearch::mcp::McpServer;
+use localsearch::server::SearchServer;
 use localsearch::{
-    LocalEmbedder, LocalSearch, LocalSearchDirs, SearchType, SqliteLocalSearchEngine,
+    DEFAULT_COLLECTION, DocumentIndexer, ListOptions, LocalEmbedder, LocalSearch, LocalSearchDirs,
+    QueryExpansion, QueryExplanation, QuerySyntax, ScoreExplanation, SearchFields, SearchOptions,
+    SearchOutcome, SearchType, SqliteLocalSearchEngine, Synonyms, Tokenizer,
 };
+use log::info;
+use std::collections::HashMap;
 use std::path::PathBuf;
-use util::{JsonFileIngestor, RawFileIngestor};
-
-use crate::util::ingest::IngestionResult;
+use util::progress::{JsonProgressObserver, ProgressBarObserver};
 
 #[derive(Parser)]
 #[command(name = "localsearch")]
@@ -17,13 +26,21 @@ struct Cli {
     command: Commands,
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
+    /// Collection to work on
+    #[clap(
+        long,
+        global = true,
+        default_value = DEFAULT_COLLECTION,
+        help = "Named collection inside the database to index into, search, list or export. Collections are created on first index or import."
+    )]
+    collection: String,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Index documents from a directory or file
     Index {
-        /// Path to directory or file to index
+        /// Path to directory or file to index, or '-' to read from stdin
         path: String,
         /// Database file path (uses project data directory by default)
         #[clap(
@@ -55,13 +72,80 @@ enum Commands {
             help = "Maximum number of tokens for the local model (default: 512). Only used with --local-model-path."
         )]
         max_tokens: Option<usize>,
-        /// File type filter: json, text
+        /// File type filter: json, text, csv, tsv
         #[clap(
             long,
             default_value = "json",
-            help = "Type of files to ingest: 'json' for JSON files, 'text' for raw text files. json is expected to contain [{\"path\": \"unique_str\", \"content\": \"document content\", \"metadata\": {\"key\": \"value\"}}]."
+            help = "Type of files to ingest: 'json' for JSON files, 'text' for raw text files, 'csv'/'tsv' for tabular files (one document per row). json is expected to contain [{\"path\": \"unique_str\", \"content\": \"document content\", \"metadata\": {\"key\": \"value\"}}]."
         )]
         file_type: String,
+        /// Column used as the document path for csv/tsv files
+        #[clap(
+            long,
+            default_value = "id",
+            help = "Column whose value becomes the document path. Only used with --file-type csv/tsv."
+        )]
+        id_column: String,
+        /// Columns concatenated into the document content for csv/tsv files
+        #[clap(
+            long,
+            default_value = "content",
+            help = "Comma-separated columns joined (one per line) into the document content. Only used with --file-type csv/tsv."
+        )]
+        content_columns: String,
+        /// Template used to build the document content for csv/tsv files
+        #[clap(
+            long,
+            help = "Template for the document content referencing columns in braces, e.g. '{title}\\n{body}'. Overrides --content-columns. Only used with --file-type csv/tsv."
+        )]
+        content_template: Option<String>,
+        /// Columns stored as metadata for csv/tsv files
+        #[clap(
+            long,
+            help = "Comma-separated columns stored as document metadata. Only used with --file-type csv/tsv."
+        )]
+        metadata_columns: Option<String>,
+        /// Document path used for raw text read from stdin
+        #[clap(
+            long = "path",
+            id = "stdin_path",
+            value_name = "PATH",
+            help = "Document path to store raw text read from stdin under. Required when indexing '-' with --file-type text."
+        )]
+        stdin_path: Option<String>,
+        /// Number of threads reading and extracting files
+        #[clap(
+            long,
+            help = "Number of worker threads reading and extracting files (default: number of CPUs)."
+        )]
+        workers: Option<usize>,
+        /// Number of documents embedded and written together
+        #[clap(
+            long,
+            default_value = "32",
+            help = "Number of documents embedded and written to the database in one batch."
+        )]
+        batch_size: usize,
+        /// Suppress progress and summary output
+        #[clap(
+            long,
+            conflicts_with = "json_progress",
+            help = "Do not print progress or a summary; failures are still reported on stderr."
+        )]
+        quiet: bool,
+        /// Report progress as JSON Lines on stdout
+        #[clap(
+            long,
+            help = "Print progress events (started, file_processed, file_failed, finished) as JSON Lines on stdout instead of a progress bar."
+        )]
+        json_progress: bool,
+        /// Tokenizer of a new full-text index
+        #[clap(
+            long,
+            value_name = "TOKENIZER",
+            help = "Tokenizer used when the collection's full-text index is created: 'ascii', 'unicode61' or 'trigram', optionally with 'porter' stemming and 'remove_diacritics', e.g. 'unicode61 remove_diacritics'. Default: 'porter ascii'."
+        )]
+        fts_tokenizer: Option<Tokenizer>,
     },
     /// Search indexed documents
     Search {
@@ -114,18 +198,571 @@ enum Commands {
         /// Output results as pretty format instead of json text
         #[clap(
             long,
-            help = "Output search results in pretty format instead of json text."
+            help = "Output search results in pretty format instead of json text."
+        )]
+        pretty: bool,
+        /// Filter search results by path pattern (supports multiple patterns)
+        #[clap(
+            long,
+            help = "Filter search results to include documents whose path contains any of these patterns. Supports multiple comma-separated patterns like 'src,test' or 'main.rs,lib.rs'. Uses case-insensitive substring matching."
+        )]
+        path_filter: Option<String>,
+        /// Search several collections at once
+        #[clap(
+            long,
+            help = "Comma-separated collections to search instead of --collection, or '*' for all collections. Results are merged by score."
+        )]
+        collections: Option<String>,
+        /// Query syntax for full-text search
+        #[clap(
+            long,
+            default_value = "plain",
+            help = "How the query is interpreted: 'plain' matches the words literally, 'advanced' allows AND, OR, NOT, parentheses, \"phrases\", prefix* and NEAR(a b, 5)."
+        )]
+        syntax: QuerySyntax,
+        /// Also match words within a few typos of the query words
+        #[clap(
+            long,
+            help = "Tolerate typos: full-text search also matches index words within one or two edits of each query word."
+        )]
+        fuzzy: bool,
+        /// Expand the full-text query with words of the best semantic matches
+        #[clap(
+            long,
+            help = "Pseudo-relevance feedback: add the most distinctive words of the top semantic matches to the full-text query."
+        )]
+        expand: bool,
+        /// Print how the query was rewritten and the results were scored
+        #[clap(
+            long,
+            help = "Show the full-text query that is run after applying synonyms, typo corrections and expansion, and how each result's score was computed: raw BM25, normalized full-text score, cosine similarity, fusion weights, rank in each list and applied filters."
+        )]
+        explain: bool,
+        /// Diversify results by maximal marginal relevance
+        #[clap(
+            long,
+            help = "Re-rank the best candidates by maximal marginal relevance so the results cover more distinct content. Between 0 and 1: 1 ranks by relevance alone, lower values increasingly skip near-duplicates of higher-ranked results (try 0.7). Uses stored embeddings."
+        )]
+        mmr_lambda: Option<f64>,
+    },
+    /// Find documents similar to an indexed document
+    Similar {
+        /// Path of the document as stored in the index
+        path: String,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Maximum number of results to return
+        #[clap(
+            long,
+            default_value = "10",
+            help = "Maximum number of similar documents to return."
+        )]
+        limit: usize,
+        /// Also match the document's most distinctive words
+        #[clap(
+            long,
+            default_value = "0",
+            help = "Number of the document's most distinctive words to also match by full-text search. 0 compares stored embeddings only; documents without an embedding need a value above 0."
+        )]
+        terms: usize,
+        /// Filter results by path pattern
+        #[clap(
+            long,
+            help = "Only return documents whose path contains any of these comma-separated patterns."
+        )]
+        path_filter: Option<String>,
+        /// Search several collections at once
+        #[clap(
+            long,
+            help = "Comma-separated collections to search instead of --collection, or '*' for all collections. The document itself is always read from --collection."
+        )]
+        collections: Option<String>,
+        /// Output results in pretty format instead of JSON
+        #[clap(long, help = "Output results in pretty format instead of JSON.")]
+        pretty: bool,
+    },
+    /// Print the embedding of a text as JSON
+    Embed {
+        /// Text to embed, or - to read it from stdin
+        text: String,
+        /// Cache directory for embedding models (uses project cache directory by default)
+        #[clap(
+            long,
+            help = "Path to the cache directory for embedding models. If not specified, uses the project cache directory."
+        )]
+        cache_dir: Option<PathBuf>,
+        /// Path to local ONNX model file
+        #[clap(
+            long,
+            help = "Path to a local ONNX embedding model file. When specified, --tokenizer-dir must also be provided."
+        )]
+        local_model_path: Option<PathBuf>,
+        /// Path to tokenizer directory containing tokenizer files
+        #[clap(
+            long,
+            help = "Path to directory containing tokenizer files (tokenizer.json, config.json, special_tokens_map.json, tokenizer_config.json). Required when --local-model-path is used."
+        )]
+        tokenizer_dir: Option<PathBuf>,
+        /// Maximum sequence length for local model
+        #[clap(
+            long,
+            help = "Maximum number of tokens for the local model (default: 512). Only used with --local-model-path."
+        )]
+        max_tokens: Option<usize>,
+    },
+    /// Print a stored document by path
+    Get {
+        /// Path of the document as stored in the index
+        path: String,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Print only the raw content instead of JSON
+        #[clap(
+            long,
+            help = "Print only the document content, without metadata or timestamps, instead of JSON."
+        )]
+        raw: bool,
+    },
+    /// List indexed documents
+    List {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Only list documents under this path prefix
+        #[clap(long, help = "Only list documents whose path starts with this prefix.")]
+        prefix: Option<String>,
+        /// Only list documents with this metadata value (repeatable)
+        #[clap(
+            long = "metadata-filter",
+            value_name = "KEY=VALUE",
+            help = "Only list documents whose metadata has this key/value pair. Can be repeated; all pairs must match."
+        )]
+        metadata_filters: Vec<String>,
+        /// Maximum number of documents to list
+        #[clap(
+            long,
+            default_value = "50",
+            help = "Maximum number of documents to list."
+        )]
+        limit: usize,
+        /// Number of documents to skip
+        #[clap(
+            long,
+            default_value = "0",
+            help = "Number of documents to skip, for paging through large indexes."
+        )]
+        offset: usize,
+        /// Output as pretty format instead of json text
+        #[clap(long, help = "Output documents in pretty format instead of json text.")]
+        pretty: bool,
+    },
+    /// Delete a document, or all documents under a path prefix
+    Delete {
+        /// Path of the document to delete
+        #[clap(required_unless_present = "prefix")]
+        path: Option<String>,
+        /// Delete all documents under this path prefix instead
+        #[clap(
+            long,
+            conflicts_with = "path",
+            help = "Delete all documents whose path starts with this prefix."
+        )]
+        prefix: Option<String>,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+    },
+    /// Show index statistics
+    Stats {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Output as pretty format instead of json text
+        #[clap(
+            long,
+            help = "Output statistics in pretty format instead of json text."
+        )]
+        pretty: bool,
+        /// Exit with an error if the index is incomplete
+        #[clap(
+            long,
+            help = "Exit with a non-zero status if the index is incomplete (full-text index out of sync or documents missing embeddings)."
+        )]
+        check: bool,
+    },
+    /// Export documents, metadata and optionally embeddings to a portable file
+    Export {
+        /// Output file, or '-' for stdout
+        #[clap(help = "File to write the export to, or '-' to write to stdout.")]
+        output: String,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Export encoding
+        #[clap(
+            long,
+            default_value = "jsonl",
+            help = "Export format: jsonl (one JSON object per line) or binary (compact, embeddings stored as raw floats)."
+        )]
+        format: ExportFormat,
+        /// Include stored embeddings
+        #[clap(
+            long,
+            help = "Include stored embeddings and the model that produced them, so importing with the same model skips embedding."
+        )]
+        embeddings: bool,
+    },
+    /// Copy the database to a backup file while it stays in use
+    Backup {
+        /// Backup file path
+        #[clap(help = "Path of the backup file to write. An existing file is replaced.")]
+        output: PathBuf,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+    },
+    /// Import an export created by the export command
+    Import {
+        /// Input file, or '-' for stdin
+        #[clap(
+            help = "Export file to import, or '-' to read from stdin. The format is detected automatically."
+        )]
+        input: String,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Cache directory for embedding models (uses project cache directory by default)
+        #[clap(
+            long,
+            help = "Path to the cache directory for embedding models. If not specified, uses the project cache directory."
+        )]
+        cache_dir: Option<PathBuf>,
+        /// Path to local ONNX model file
+        #[clap(
+            long,
+            help = "Path to a local ONNX embedding model file. When specified, --tokenizer-dir must also be provided."
+        )]
+        local_model_path: Option<PathBuf>,
+        /// Path to tokenizer directory containing tokenizer files
+        #[clap(
+            long,
+            help = "Path to directory containing tokenizer files (tokenizer.json, config.json, special_tokens_map.json, tokenizer_config.json). Required when --local-model-path is used."
+        )]
+        tokenizer_dir: Option<PathBuf>,
+        /// Maximum sequence length for local model
+        #[clap(
+            long,
+            help = "Maximum number of tokens for the local model (default: 512). Only used with --local-model-path."
+        )]
+        max_tokens: Option<usize>,
+    },
+    /// List, create or drop named collections
+    Collections {
+        #[command(subcommand)]
+        action: CollectionsAction,
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            global = true,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+    },
+    /// Show or change the fields and tokenizer of the full-text index
+    Fields {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// BM25 weight of the document content
+        #[clap(
+            long,
+            help = "Weight of matches in the document content (default: 1). Setting any option replaces all indexed fields and rebuilds the full-text index."
+        )]
+        content_weight: Option<f64>,
+        /// Index document paths with this weight
+        #[clap(
+            long = "path",
+            value_name = "WEIGHT",
+            help = "Index the words of document paths with this weight, searchable as path:word."
+        )]
+        path_weight: Option<f64>,
+        /// Index a metadata value with a weight (repeatable)
+        #[clap(
+            long = "metadata",
+            value_name = "KEY=WEIGHT",
+            help = "Index the metadata value under KEY with this weight, searchable as KEY:word. Can be repeated."
+        )]
+        metadata: Vec<String>,
+        /// Change the tokenizer of the full-text index
+        #[clap(
+            long,
+            value_name = "TOKENIZER",
+            help = "Reindex the collection with this tokenizer, e.g. 'unicode61 remove_diacritics' or 'trigram'."
+        )]
+        fts_tokenizer: Option<Tokenizer>,
+    },
+    /// Show or set the synonym groups applied to full-text queries
+    Synonyms {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// File with one group of comma-separated synonyms per line
+        #[clap(
+            long,
+            help = "Replace the synonyms with the groups in this file, one group of comma-separated terms per line (e.g. 'k8s, kubernetes'). Lines starting with '#' are ignored. Use '-' to read stdin."
+        )]
+        file: Option<PathBuf>,
+        /// Remove all synonyms
+        #[clap(long, conflicts_with = "file", help = "Remove all synonym groups.")]
+        clear: bool,
+    },
+    /// Serve search and document requests over HTTP
+    Serve {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file to serve. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Cache directory for embedding models (uses project cache directory by default)
+        #[clap(
+            long,
+            help = "Path to the cache directory for embedding models. If not specified, uses the project cache directory."
+        )]
+        cache_dir: Option<PathBuf>,
+        /// Path to local ONNX model file
+        #[clap(
+            long,
+            help = "Path to a local ONNX embedding model file. When specified, --tokenizer-dir must also be provided."
+        )]
+        local_model_path: Option<PathBuf>,
+        /// Path to tokenizer directory containing tokenizer files
+        #[clap(
+            long,
+            help = "Path to directory containing tokenizer files (tokenizer.json, config.json, special_tokens_map.json, tokenizer_config.json). Required when --local-model-path is used."
+        )]
+        tokenizer_dir: Option<PathBuf>,
+        /// Maximum sequence length for local model
+        #[clap(
+            long,
+            help = "Maximum number of tokens for the local model (default: 512). Only used with --local-model-path."
+        )]
+        max_tokens: Option<usize>,
+        /// Address to listen on
+        #[clap(
+            long,
+            default_value = "127.0.0.1",
+            help = "Address to listen on. Use 0.0.0.0 to accept connections from other machines."
+        )]
+        host: String,
+        /// Port to listen on
+        #[clap(long, default_value = "8080", help = "Port to listen on.")]
+        port: u16,
+    },
+    /// Serve the index to AI assistants over the Model Context Protocol (stdio)
+    Mcp {
+        /// Database file path (uses project data directory by default)
+        #[clap(
+            long,
+            help = "Path to the SQLite database file to serve. If not specified, uses the project data directory."
+        )]
+        db: Option<String>,
+        /// Cache directory for embedding models (uses project cache directory by default)
+        #[clap(
+            long,
+            help = "Path to the cache directory for embedding models. If not specified, uses the project cache directory."
+        )]
+        cache_dir: Option<PathBuf>,
+        /// Path to local ONNX model file
+        #[clap(
+            long,
+            help = "Path to a local ONNX embedding model file. When specified, --tokenizer-dir must also be provided."
+        )]
+        local_model_path: Option<PathBuf>,
+        /// Path to tokenizer directory containing tokenizer files
+        #[clap(
+            long,
+            help = "Path to directory containing tokenizer files (tokenizer.json, config.json, special_tokens_map.json, tokenizer_config.json). Required when --local-model-path is used."
+        )]
+        tokenizer_dir: Option<PathBuf>,
+        /// Maximum sequence length for local model
+        #[clap(
+            long,
+            help = "Maximum number of tokens for the local model (default: 512). Only used with --local-model-path."
         )]
-        pretty: bool,
-        /// Filter search results by path pattern (supports multiple patterns)
+        max_tokens: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollectionsAction {
+    /// List collections with their document counts
+    List {
+        /// Output as pretty format instead of json text
         #[clap(
             long,
-            help = "Filter search results to include documents whose path contains any of these patterns. Supports multiple comma-separated patterns like 'src,test' or 'main.rs,lib.rs'. Uses case-insensitive substring matching."
+            help = "Output collections in pretty format instead of json text."
         )]
-        path_filter: Option<String>,
+        pretty: bool,
+    },
+    /// Create an empty collection
+    Create {
+        /// Collection name (lowercase letters, digits and '_')
+        name: String,
+    },
+    /// Drop a collection and all of its documents
+    Drop {
+        /// Collection name
+        name: String,
     },
 }
 
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn parse_metadata_filters(filters: &[String]) -> anyhow::Result<Option<HashMap<String, String>>> {
+    if filters.is_empty() {
+        return Ok(None);
+    }
+    filters
+        .iter()
+        .map(|filter| {
+            filter
+                .split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Invalid metadata filter '{}'. Use KEY=VALUE.", filter)
+                })
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()
+        .map(Some)
+}
+
+fn parse_search_fields(
+    content_weight: Option<f64>,
+    path_weight: Option<f64>,
+    metadata: &[String],
+) -> anyhow::Result<SearchFields> {
+    let mut fields = SearchFields::new();
+    if let Some(weight) = content_weight {
+        fields = fields.with_content_weight(weight);
+    }
+    if let Some(weight) = path_weight {
+        fields = fields.with_path(weight);
+    }
+    for field in metadata {
+        let (key, weight) = field
+            .split_once('=')
+            .and_then(|(key, weight)| Some((key.trim(), weight.trim().parse().ok()?)))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Invalid metadata field '{}'. Use KEY=WEIGHT.", field)
+            })?;
+        fields = fields.with_metadata(key, weight);
+    }
+    Ok(fields)
+}
+
+/// Prints the rewritten full-text query of one collection for `search --explain`.
+fn print_query_explanation(explanation: &QueryExplanation, show_collection: bool) {
+    if show_collection {
+        println!("Query in collection '{}':", explanation.collection);
+    }
+    println!(
+        "Full-text query: {}",
+        explanation
+            .fts_query
+            .as_deref()
+            .unwrap_or("(no searchable words)")
+    );
+    for (term, synonyms) in &explanation.synonyms {
+        println!("   Synonyms of '{}': {}", term, synonyms.join(", "));
+    }
+    for (word, corrections) in &explanation.corrections {
+        println!("   Corrections of '{}': {}", word, corrections.join(", "));
+    }
+    if !explanation.expansion_terms.is_empty() {
+        println!(
+            "   Expansion terms: {}",
+            explanation.expansion_terms.join(", ")
+        );
+    }
+    println!();
+}
+
+/// Prints the score breakdown of one result for `search --explain`.
+fn print_score_explanation(explanation: &ScoreExplanation) {
+    let rank = |rank: Option<usize>| rank.map_or("-".to_string(), |rank| format!("#{}", rank));
+    if let Some(bm25) = explanation.bm25 {
+        println!(
+            "   Full-text: rank {}, BM25 {:.4}, softmax {:.4}{}",
+            rank(explanation.fts_rank),
+            bm25,
+            explanation.fts_softmax.unwrap_or_default(),
+            explanation
+                .fts_normalized
+                .map(|score| format!(", normalized {:.4}", score))
+                .unwrap_or_default()
+        );
+    }
+    if let Some(similarity) = explanation.cosine_similarity {
+        println!(
+            "   Semantic: rank {}, cosine similarity {:.4}",
+            rank(explanation.semantic_rank),
+            similarity
+        );
+    }
+    println!(
+        "   Weights: full-text {}, semantic {}",
+        explanation.fts_weight, explanation.semantic_weight
+    );
+    if let Some(redundancy) = explanation.redundancy {
+        println!(
+            "   Diversity: similarity {:.4} to the results above",
+            redundancy
+        );
+    }
+    if !explanation.filters.is_empty() {
+        println!("   Filters: {}", explanation.filters.join(", "));
+    }
+}
+
 fn validate_db_presence(db_path: &str) -> anyhow::Result<()> {
     if !std::path::Path::new(db_path).exists() {
         return Err(anyhow::anyhow!(
@@ -136,6 +773,23 @@ fn validate_db_presence(db_path: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Opens the engine on an existing collection.
+fn open_collection(
+    db_path: &str,
+    embedder: Option<LocalEmbedder>,
+    collection: &str,
+) -> anyhow::Result<SqliteLocalSearchEngine> {
+    let engine = SqliteLocalSearchEngine::new(db_path, embedder)?.with_collection(collection)?;
+    if !engine.collection_exists(collection)? {
+        return Err(anyhow::anyhow!(
+            "Collection '{}' does not exist in {}. Index documents into it first.",
+            collection,
+            db_path
+        ));
+    }
+    Ok(engine)
+}
+
 fn get_database_path(db_option: Option<String>) -> anyhow::Result<String> {
     match db_option {
         Some(path) => Ok(path),
@@ -157,9 +811,10 @@ fn create_embedder(
     match (local_model_path, tokenizer_dir) {
         (Some(model_path), Some(tokenizer_path)) => {
             // Use local model
-            println!("Using local ONNX model: {:?}", model_path);
-            println!("Using tokenizer directory: {:?}", tokenizer_path);
+            info!("Using local ONNX model: {:?}", model_path);
+            info!("Using tokenizer directory: {:?}", tokenizer_path);
             LocalEmbedder::new_with_local_model(model_path, tokenizer_path, max_tokens)
+                .map_err(Into::into)
         }
         (Some(_), None) => Err(anyhow::anyhow!(
             "--tokenizer-dir must be specified when using --local-model-path"
@@ -171,15 +826,15 @@ fn create_embedder(
             // Use default or cache directory model
             match cache_dir {
                 Some(cache_path) => {
-                    println!(
+                    info!(
                         "Using pre-built model with cache directory: {:?}",
                         cache_path
                     );
-                    LocalEmbedder::new_with_cache_dir(cache_path)
+                    LocalEmbedder::new_with_cache_dir(cache_path).map_err(Into::into)
                 }
                 None => {
-                    println!("Using default pre-built model");
-                    LocalEmbedder::new_with_default_model()
+                    info!("Using default pre-built model");
+                    LocalEmbedder::new_with_default_model().map_err(Into::into)
                 }
             }
         }
@@ -192,6 +847,7 @@ fn main() -> anyhow::Result<()> {
         .filter_level(cli.verbose.log_level_filter())
         .init();
 
+    let collection = cli.collection;
     match cli.command {
         Commands::Index {
             path,
@@ -201,62 +857,116 @@ fn main() -> anyhow::Result<()> {
             tokenizer_dir,
             max_tokens,
             file_type,
+            id_column,
+            content_columns,
+            content_template,
+            metadata_columns,
+            stdin_path,
+            workers,
+            batch_size,
+            quiet,
+            json_progress,
+            fts_tokenizer,
         } => {
             let db_path = get_database_path(db)?;
-            println!(
-                "Indexing documents from: {} and storing in database: {}",
-                path, db_path
-            );
+            let human_output = !quiet && !json_progress;
+            if human_output {
+                println!(
+                    "Indexing documents from: {} and storing in database: {}",
+                    if path == "-" { "stdin" } else { &path },
+                    db_path
+                );
+            }
+            let observer = || -> Box<dyn IngestObserver> {
+                if json_progress {
+                    Box::new(JsonProgressObserver)
+                } else if quiet {
+                    Box::new(NoopObserver)
+                } else {
+                    Box::new(ProgressBarObserver::new())
+                }
+            };
 
             // Initialize the embedder with all options
             let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
 
             // Initialize the search engine
-            let engine = SqliteLocalSearchEngine::new(&db_path, Some(embedder))?;
+            let mut engine = SqliteLocalSearchEngine::new(&db_path, Some(embedder))?
+                .with_collection(&collection)?;
+            if let Some(tokenizer) = fts_tokenizer {
+                engine = engine.with_tokenizer(tokenizer)?;
+            }
             engine.create_table()?;
             let boxed_engine = Box::new(engine);
+            let pipeline = PipelineConfig {
+                workers: workers.unwrap_or(PipelineConfig::default().workers),
+                batch_size,
+                ..PipelineConfig::default()
+            };
 
-            // Choose the appropriate ingestor based on file type
-            let ingestion_result: IngestionResult = match file_type.as_str() {
-                "json" => {
-                    let ingestor = JsonFileIngestor::new(boxed_engine);
-                    ingestor.ingest(&path)?
-                }
-                "text" => {
-                    let ingestor = RawFileIngestor::new(boxed_engine);
-                    ingestor.ingest(&path, |file_path| {
-                        // Accept common text file extensions
-                        if let Some(ext) = file_path.extension().and_then(|s| s.to_str()) {
-                            matches!(
-                                ext,
-                                "txt"
-                                    | "md"
-                                    | "py"
-                                    | "rs"
-                                    | "js"
-                                    | "ts"
-                                    | "html"
-                                    | "css"
-                                    | "json"
-                                    | "xml"
-                                    | "yaml"
-                                    | "yml"
-                            )
-                        } else {
-                            false
-                        }
-                    })?
+            // Choose the appropriate extractor based on file type
+            let ingestor = Ingestor::new(boxed_engine)
+                .with_pipeline(pipeline)
+                .with_observer(observer());
+            let (ingestor, stdin_key) = match file_type.as_str() {
+                "json" => (ingestor.with_extractor(JsonExtractor::new()), "json"),
+                "text" => (ingestor.with_extractor(TextExtractor::new()), "txt"),
+                "csv" | "tsv" => {
+                    let mapping = CsvColumnMapping {
+                        delimiter: if file_type == "csv" { b',' } else { b'\t' },
+                        id_column,
+                        content_columns: split_list(&content_columns),
+                        content_template,
+                        metadata_columns: metadata_columns
+                            .as_deref()
+                            .map(split_list)
+                            .unwrap_or_default(),
+                    };
+                    (
+                        ingestor.with_extractor(CsvExtractor::new(mapping)),
+                        file_type.as_str(),
+                    )
                 }
                 _ => {
-                    // Return error for unsupported file types
-                    println!(
-                        "Unsupported file type: {}. Use 'json' or 'text'.",
+                    return Err(anyhow::anyhow!(
+                        "Unsupported file type: {}. Use 'json', 'text', 'csv' or 'tsv'.",
                         file_type
-                    );
-                    IngestionResult::new()
+                    ));
                 }
             };
 
+            let ingestion_result = if path == "-" {
+                let source = if file_type == "text" {
+                    stdin_path.ok_or_else(|| {
+                        anyhow::anyhow!("--path must be specified when indexing text from stdin")
+                    })?
+                } else {
+                    "<stdin>".to_string()
+                };
+                ingestor.ingest_reader(std::io::stdin().lock(), stdin_key, &source)?
+            } else {
+                ingestor.ingest(&path)?
+            };
+
+            if !human_output {
+                // JSON mode already reported everything as events
+                if quiet {
+                    for file_path in &ingestion_result.failed_files {
+                        eprintln!("Failed: {}", file_path);
+                    }
+                }
+                return Ok(());
+            }
+
+            if ingestion_result.document_count > 0 && !ingestion_result.elapsed.is_zero() {
+                println!(
+                    "Wrote {} document(s) in {:.2}s ({:.1} documents/s)",
+                    ingestion_result.document_count,
+                    ingestion_result.elapsed.as_secs_f64(),
+                    ingestion_result.documents_per_second()
+                );
+            }
+
             if !ingestion_result.failed_files.is_empty() {
                 println!("Failed files:");
                 for file_path in &ingestion_result.failed_files {
@@ -280,6 +990,12 @@ fn main() -> anyhow::Result<()> {
             limit,
             pretty,
             path_filter,
+            collections,
+            syntax,
+            fuzzy,
+            expand,
+            explain,
+            mmr_lambda,
         } => {
             if pretty {
                 println!("Searching for: \"{}\"", query);
@@ -292,28 +1008,32 @@ fn main() -> anyhow::Result<()> {
             let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
 
             // Initialize the search engine
-            let engine = SqliteLocalSearchEngine::new(&db_path, Some(embedder))?;
+            let engine = match collections {
+                Some(_) => SqliteLocalSearchEngine::new(&db_path, Some(embedder))?,
+                None => open_collection(&db_path, Some(embedder), &collection)?,
+            };
 
             // Parse search type
-            let search_type_enum = match search_type.as_str() {
-                "fulltext" | "fts" => SearchType::FullText,
-                "semantic" | "embedding" => SearchType::Semantic,
-                _ => SearchType::Hybrid,
-            };
+            let search_type_enum = search_type.parse().unwrap_or(SearchType::Hybrid);
 
             // Perform search
-            let path_filters = path_filter.as_ref().map(|f| {
-                f.split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect::<Vec<String>>()
-            });
-            let results = engine.search(
-                &query,
-                search_type_enum,
-                Some(limit as i8),
-                path_filters.as_deref(),
-            )?;
+            let options = SearchOptions {
+                limit: Some(limit),
+                path_filters: path_filter.as_deref().map(split_list),
+                collections: collections.as_deref().map(split_list),
+                query_syntax: syntax,
+                fuzzy,
+                query_expansion: expand.then(QueryExpansion::default),
+                explain,
+                mmr_lambda,
+                ..SearchOptions::default()
+            };
+            let SearchOutcome {
+                results,
+                suggestion,
+                query_explanations: explanations,
+            } = engine.search_with_details(&query, search_type_enum, &options)?;
+            let show_collection = options.collections.is_some();
 
             if !pretty {
                 // Output as JSON
@@ -321,13 +1041,17 @@ fn main() -> anyhow::Result<()> {
                     "query": query,
                     "search_type": search_type,
                     "results_count": results.len(),
+                    "suggestion": suggestion,
+                    "explain": explain.then_some(&explanations),
                     "results": results.iter().take(limit).map(|result| {
                         serde_json::json!({
                             "path": result.path,
+                            "collection": result.collection,
                             "final_score": result.final_score,
                             "fts_score": result.fts_score,
                             "semantic_score": result.semantic_score,
-                            "metadata": result.metadata
+                            "metadata": result.metadata,
+                            "explanation": result.explanation
                         })
                     }).collect::<Vec<_>>()
                 });
@@ -335,6 +1059,12 @@ fn main() -> anyhow::Result<()> {
                 return Ok(());
             }
 
+            if let Some(suggestion) = &suggestion {
+                println!("Did you mean: \"{}\"?", suggestion);
+            }
+            for explanation in &explanations {
+                print_query_explanation(explanation, show_collection);
+            }
             if results.is_empty() {
                 println!("No results found.");
                 return Ok(());
@@ -347,6 +1077,9 @@ fn main() -> anyhow::Result<()> {
             for (i, result) in results.iter().take(limit).enumerate() {
                 println!("Result {} - Score: {:.4}", i + 1, result.final_score);
                 println!("   Path: {}", result.path);
+                if show_collection {
+                    println!("   Collection: {}", result.collection);
+                }
 
                 if let Some(fts_score) = result.fts_score {
                     println!("   FTS Score: {:.4}", fts_score);
@@ -362,9 +1095,425 @@ fn main() -> anyhow::Result<()> {
                     println!("   Metadata: {:?}", metadata);
                 }
 
+                if let Some(explanation) = &result.explanation {
+                    print_score_explanation(explanation);
+                }
+
+                println!();
+            }
+        }
+        Commands::Similar {
+            path,
+            db,
+            limit,
+            terms,
+            path_filter,
+            collections,
+            pretty,
+        } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+
+            // Stored embeddings are compared, so no model is loaded
+            let engine = open_collection(&db_path, None, &collection)?;
+            let options = SearchOptions {
+                limit: Some(limit),
+                path_filters: path_filter.as_deref().map(split_list),
+                collections: collections.as_deref().map(split_list),
+                ..SearchOptions::default()
+            };
+            let results = engine.find_similar(&path, terms, &options)?;
+
+            if !pretty {
+                let json_output = serde_json::json!({
+                    "path": path,
+                    "results_count": results.len(),
+                    "results": results,
+                });
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+                return Ok(());
+            }
+            if results.is_empty() {
+                println!("No similar documents found.");
+                return Ok(());
+            }
+            println!("Documents similar to {}:", path);
+            println!();
+            for (i, result) in results.iter().enumerate() {
+                println!("Result {} - Score: {:.4}", i + 1, result.final_score);
+                println!("   Path: {}", result.path);
+                if options.collections.is_some() {
+                    println!("   Collection: {}", result.collection);
+                }
+                println!();
+            }
+        }
+        Commands::Embed {
+            text,
+            cache_dir,
+            local_model_path,
+            tokenizer_dir,
+            max_tokens,
+        } => {
+            let text = if text == "-" {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                text
+            };
+            let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
+            let embedding = embedder.embed_text(&text)?;
+            let json_output = serde_json::json!({
+                "model": embedder.model_id(),
+                "dimension": embedding.len(),
+                "embedding": embedding,
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        }
+        Commands::Get { path, db, raw } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+
+            // Reading stored documents needs no embedder
+            let engine = open_collection(&db_path, None, &collection)?;
+            let document = engine
+                .get_document(&path)?
+                .ok_or_else(|| anyhow::anyhow!("Document '{}' not found in {}", path, db_path))?;
+
+            if raw {
+                print!("{}", document.content);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&document)?);
+            }
+        }
+        Commands::List {
+            db,
+            prefix,
+            metadata_filters,
+            limit,
+            offset,
+            pretty,
+        } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+            let documents = engine.list_documents(&ListOptions {
+                path_prefix: prefix,
+                metadata_filters: parse_metadata_filters(&metadata_filters)?,
+                limit: Some(limit),
+                offset,
+            })?;
+
+            if !pretty {
+                println!("{}", serde_json::to_string_pretty(&documents)?);
+                return Ok(());
+            }
+            if documents.is_empty() {
+                println!("No documents found.");
+                return Ok(());
+            }
+            for document in &documents {
+                println!("{}", document.path);
+                if let Some(ref metadata) = document.metadata
+                    && !metadata.is_empty()
+                {
+                    println!("   Metadata: {:?}", metadata);
+                }
+            }
+            if documents.len() == limit {
                 println!();
+                println!(
+                    "Showing documents {}-{}. Use --offset {} for more.",
+                    offset + 1,
+                    offset + limit,
+                    offset + limit
+                );
+            }
+        }
+        Commands::Delete { path, prefix, db } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+
+            match (path, prefix) {
+                (_, Some(prefix)) => {
+                    let deleted = engine.delete_prefix(&prefix)?;
+                    println!(
+                        "Deleted {} document(s) with path prefix '{}'.",
+                        deleted, prefix
+                    );
+                }
+                (Some(path), None) => {
+                    if engine.get_document(&path)?.is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Document '{}' not found in {}",
+                            path,
+                            db_path
+                        ));
+                    }
+                    engine.delete_document(&path)?;
+                    println!("Deleted document '{}'.", path);
+                }
+                (None, None) => unreachable!("clap requires a path or --prefix"),
+            }
+        }
+        Commands::Stats { db, pretty, check } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+            let stats = engine.stats()?;
+
+            if !pretty {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("Database: {}", db_path);
+                println!("   Size: {} bytes", stats.db_size_bytes);
+                println!(
+                    "   Documents: {} ({} bytes of content)",
+                    stats.document_count, stats.total_content_bytes
+                );
+                println!(
+                    "   Embeddings: {} ({} documents without an embedding)",
+                    stats.embedding_count, stats.documents_missing_embeddings
+                );
+                if let Some(ref model) = stats.embedding_model {
+                    println!("   Embedding model: {}", model);
+                }
+                match stats.embedding_dimension {
+                    Some(dimension) => println!("   Embedding dimension: {}", dimension),
+                    None => println!("   Embedding dimension: none stored"),
+                }
+                println!(
+                    "   Full-text index: {} rows (integrity check {})",
+                    stats.fts_row_count,
+                    if stats.fts_integrity_ok {
+                        "passed"
+                    } else {
+                        "failed"
+                    }
+                );
+                if let Some(last_updated_at) = stats.last_updated_at {
+                    println!("   Last updated: {:.0} (unix time)", last_updated_at);
+                }
+                println!(
+                    "   Status: {}",
+                    if stats.is_healthy() {
+                        "ok"
+                    } else {
+                        "incomplete, consider reindexing"
+                    }
+                );
+            }
+
+            if check && !stats.is_healthy() {
+                return Err(anyhow::anyhow!(
+                    "Index {} is incomplete: {} of {} documents in the full-text index, {} without an embedding",
+                    db_path,
+                    stats.fts_row_count,
+                    stats.document_count,
+                    stats.documents_missing_embeddings
+                ));
+            }
+        }
+        Commands::Export {
+            output,
+            db,
+            format,
+            embeddings,
+        } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+            let options = ExportOptions {
+                format,
+                include_embeddings: embeddings,
+            };
+
+            if output == "-" {
+                engine.export_to(std::io::stdout().lock(), &options)?;
+            } else {
+                let file = std::fs::File::create(&output)
+                    .map_err(|e| anyhow::anyhow!("Failed to create '{}': {}", output, e))?;
+                let count = engine.export_to(std::io::BufWriter::new(file), &options)?;
+                println!("Exported {} documents to {}", count, output);
+            }
+        }
+        Commands::Backup { output, db } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            // Backups cover the whole database, all collections included
+            let engine = SqliteLocalSearchEngine::new(&db_path, None)?;
+            engine.backup_to(&output)?;
+            println!("Backed up {} to {}", db_path, output.display());
+        }
+        Commands::Import {
+            input,
+            db,
+            cache_dir,
+            local_model_path,
+            tokenizer_dir,
+            max_tokens,
+        } => {
+            let db_path = get_database_path(db)?;
+
+            let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
+            let engine = SqliteLocalSearchEngine::new(&db_path, Some(embedder))?
+                .with_collection(&collection)?;
+            if !engine.collection_exists(&collection)? {
+                engine.create_table()?;
+            }
+
+            let summary = if input == "-" {
+                engine.import_from(std::io::stdin().lock())?
+            } else {
+                let file = std::fs::File::open(&input)
+                    .map_err(|e| anyhow::anyhow!("Failed to open '{}': {}", input, e))?;
+                engine.import_from(std::io::BufReader::new(file))?
+            };
+            println!(
+                "Imported {} documents into {} ({} embeddings reused, {} computed)",
+                summary.document_count,
+                db_path,
+                summary.reused_embeddings,
+                summary.computed_embeddings
+            );
+        }
+        Commands::Collections { action, db } => {
+            let db_path = get_database_path(db)?;
+            let engine = SqliteLocalSearchEngine::new(&db_path, None)?;
+            match action {
+                CollectionsAction::List { pretty } => {
+                    let collections = engine.list_collections()?;
+                    if !pretty {
+                        println!("{}", serde_json::to_string_pretty(&collections)?);
+                    } else if collections.is_empty() {
+                        println!("No collections found.");
+                    } else {
+                        for info in &collections {
+                            println!("{} ({} documents)", info.name, info.document_count);
+                            if let Some(ref model) = info.embedding_model {
+                                println!("   Embedding model: {}", model);
+                            }
+                        }
+                    }
+                }
+                CollectionsAction::Create { name } => {
+                    engine.create_collection(&name)?;
+                    println!("Created collection '{}'.", name);
+                }
+                CollectionsAction::Drop { name } => {
+                    engine.drop_collection(&name)?;
+                    println!("Dropped collection '{}'.", name);
+                }
+            }
+        }
+        Commands::Fields {
+            db,
+            content_weight,
+            path_weight,
+            metadata,
+            fts_tokenizer,
+        } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+            let changed = fts_tokenizer.is_some()
+                || content_weight.is_some()
+                || path_weight.is_some()
+                || !metadata.is_empty();
+            if let Some(tokenizer) = fts_tokenizer {
+                engine.set_tokenizer(tokenizer)?;
+                println!(
+                    "Reindexed collection '{}' with tokenizer '{}'",
+                    collection, tokenizer
+                );
+            }
+            if content_weight.is_some() || path_weight.is_some() || !metadata.is_empty() {
+                let fields = parse_search_fields(content_weight, path_weight, &metadata)?;
+                engine.set_search_fields(&fields)?;
+                println!(
+                    "Indexed fields of collection '{}': {}",
+                    collection,
+                    fields.names().join(", ")
+                );
+            }
+            if !changed {
+                let config = serde_json::json!({
+                    "tokenizer": engine.tokenizer()?.to_string(),
+                    "fields": engine.search_fields()?,
+                });
+                println!("{}", serde_json::to_string_pretty(&config)?);
             }
         }
+        Commands::Synonyms { db, file, clear } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+            let engine = open_collection(&db_path, None, &collection)?;
+            if clear {
+                engine.set_synonyms(&Synonyms::new())?;
+                println!("Removed the synonyms of collection '{}'", collection);
+            } else if let Some(file) = file {
+                let text = if file.as_os_str() == "-" {
+                    std::io::read_to_string(std::io::stdin())?
+                } else {
+                    std::fs::read_to_string(&file)?
+                };
+                let synonyms: Synonyms = text.parse()?;
+                engine.set_synonyms(&synonyms)?;
+                println!(
+                    "Set {} synonym groups in collection '{}'",
+                    synonyms.groups.len(),
+                    collection
+                );
+            } else {
+                print!("{}", engine.synonyms()?);
+            }
+        }
+        Commands::Serve {
+            db,
+            cache_dir,
+            local_model_path,
+            tokenizer_dir,
+            max_tokens,
+            host,
+            port,
+        } => {
+            let db_path = get_database_path(db)?;
+
+            // Load the model once; it stays in memory for all requests
+            let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
+            let engine = SqliteLocalSearchEngine::new(&db_path, Some(embedder))?
+                .with_collection(&collection)?;
+            if !engine.collection_exists(&collection)? {
+                engine.create_table()?;
+            }
+
+            let server = SearchServer::bind(engine, &format!("{}:{}", host, port))?;
+            println!(
+                "Serving database {} on http://{}",
+                db_path,
+                server
+                    .local_addr()
+                    .map(|addr| addr.to_string())
+                    .unwrap_or_else(|| format!("{}:{}", host, port))
+            );
+            server.run();
+        }
+        Commands::Mcp {
+            db,
+            cache_dir,
+            local_model_path,
+            tokenizer_dir,
+            max_tokens,
+        } => {
+            let db_path = get_database_path(db)?;
+            validate_db_presence(&db_path)?;
+
+            // stdout carries the protocol, so nothing else may be printed there
+            let embedder = create_embedder(cache_dir, local_model_path, tokenizer_dir, max_tokens)?;
+            let engine = open_collection(&db_path, Some(embedder), &collection)?;
+            info!("Serving database {} over MCP on stdio", db_path);
+            McpServer::new(engine).run(std::io::stdin().lock(), std::io::stdout().lock())?;
+        }
     }
     Ok(())
 }
@@ -1,3 +1 @@
-pub mod ingest;
-
-pub use crate::util::ingest::{JsonFileIngestor, RawFileIngestor};
+pub mod progress;
@@ -0,0 +1,100 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use localsearch::ingest::{IngestObserver, IngestionResult};
+use std::io::Write;
+use std::path::Path;
+
+/// Shows ingestion progress as a progress bar on stderr.
+pub struct ProgressBarObserver {
+    bar: ProgressBar,
+}
+
+impl ProgressBarObserver {
+    pub fn new() -> Self {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner} [{elapsed_precise}] {bar:40} {pos}/{len} files {wide_msg}",
+            )
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+        );
+        ProgressBarObserver { bar }
+    }
+}
+
+impl IngestObserver for ProgressBarObserver {
+    fn on_started(&self, total_files: usize) {
+        self.bar.set_length(total_files as u64);
+    }
+
+    fn on_file_processed(&self, path: &Path, _documents: usize) {
+        self.bar.set_message(
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        );
+        self.bar.inc(1);
+    }
+
+    fn on_file_failed(&self, path: &Path, error: &anyhow::Error) {
+        self.bar.println(format!(
+            "✗ Failed to process: {} - {}",
+            path.display(),
+            error
+        ));
+        self.bar.inc(1);
+    }
+
+    fn on_finished(&self, _result: &IngestionResult) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Emits ingestion events as JSON Lines on stdout for tools wrapping the CLI.
+pub struct JsonProgressObserver;
+
+impl JsonProgressObserver {
+    fn emit(&self, event: serde_json::Value) {
+        let mut stdout = std::io::stdout().lock();
+        // Progress is best effort; a closed pipe must not abort ingestion
+        let _ = writeln!(stdout, "{}", event);
+        let _ = stdout.flush();
+    }
+}
+
+impl IngestObserver for JsonProgressObserver {
+    fn on_started(&self, total_files: usize) {
+        self.emit(serde_json::json!({
+            "event": "started",
+            "total_files": total_files,
+        }));
+    }
+
+    fn on_file_processed(&self, path: &Path, documents: usize) {
+        self.emit(serde_json::json!({
+            "event": "file_processed",
+            "path": path.to_string_lossy(),
+            "documents": documents,
+        }));
+    }
+
+    fn on_file_failed(&self, path: &Path, error: &anyhow::Error) {
+        self.emit(serde_json::json!({
+            "event": "file_failed",
+            "path": path.to_string_lossy(),
+            "error": error.to_string(),
+        }));
+    }
+
+    fn on_finished(&self, result: &IngestionResult) {
+        self.emit(serde_json::json!({
+            "event": "finished",
+            "indexed_count": result.indexed_count,
+            "failed_count": result.failed_count,
+            "document_count": result.document_count,
+            "total_document_count": result.total_document_count,
+            "elapsed_seconds": result.elapsed.as_secs_f64(),
+            "documents_per_second": result.documents_per_second(),
+        }));
+    }
+}
@@ -1,10 +1,10 @@
 use crate::config::LocalSearchDirs;
-use anyhow::Result;
+use crate::error::{LocalSearchError, Result};
 use fastembed::{
     InitOptions, InitOptionsUserDefined, TextEmbedding, TokenizerFiles, UserDefinedEmbeddingModel,
 };
 use log::{debug, info};
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, sync::OnceLock};
 
 /// Local text embedding service using FastEmbed models.
 ///
@@ -13,6 +13,8 @@ use std::{fs, path::PathBuf};
 /// tokenizer.json, config.json, special_tokens_map.json, and tokenizer_config.json.
 pub struct LocalEmbedder {
     model: TextEmbedding,
+    model_id: String,
+    dimension: OnceLock<usize>,
 }
 
 impl LocalEmbedder {
@@ -28,16 +30,27 @@ impl LocalEmbedder {
             Some(dir) => dir,
             None => {
                 let dirs = LocalSearchDirs::new();
-                dirs.ensure_cache_dir()?
+                dirs.ensure_cache_dir()
+                    .map_err(|e| LocalSearchError::io("Failed to create cache directory", e))?
             }
         };
 
         let init_options = InitOptions::new(model_name.clone()).with_cache_dir(cache_dir);
-        let model = TextEmbedding::try_new(init_options)?;
+        let model = TextEmbedding::try_new(init_options)
+            .map_err(|e| LocalSearchError::embedding("Failed to load embedding model", e))?;
 
         info!("Initialized embedding model: {:?}", model_name);
 
-        Ok(LocalEmbedder { model })
+        let dimension = OnceLock::new();
+        if let Ok(info) = TextEmbedding::get_model_info(&model_name) {
+            let _ = dimension.set(info.dim);
+        }
+
+        Ok(LocalEmbedder {
+            model,
+            model_id: format!("{:?}", model_name),
+            dimension,
+        })
     }
 
     /// Creates a new embedder with local model files.
@@ -57,25 +70,26 @@ impl LocalEmbedder {
     ) -> Result<Self> {
         // Load ONNX model file
         let onnx_file = fs::read(&onnx_model_path).map_err(|e| {
-            anyhow::anyhow!(
-                "Failed to read ONNX model from {:?}: {}",
-                onnx_model_path,
-                e
+            LocalSearchError::io(
+                format!("Failed to read ONNX model from {:?}", onnx_model_path),
+                e,
             )
         })?;
 
         // Load tokenizer files
         let tokenizer_files = TokenizerFiles {
             tokenizer_file: fs::read(tokenizer_dir.join("tokenizer.json"))
-                .map_err(|e| anyhow::anyhow!("Failed to read tokenizer.json: {}", e))?,
+                .map_err(|e| LocalSearchError::io("Failed to read tokenizer.json", e))?,
             config_file: fs::read(tokenizer_dir.join("config.json"))
-                .map_err(|e| anyhow::anyhow!("Failed to read config.json: {}", e))?,
+                .map_err(|e| LocalSearchError::io("Failed to read config.json", e))?,
             special_tokens_map_file: fs::read(tokenizer_dir.join("special_tokens_map.json"))
-                .map_err(|e| anyhow::anyhow!("Failed to read special_tokens_map.json: {}", e))?,
+                .map_err(|e| LocalSearchError::io("Failed to read special_tokens_map.json", e))?,
             tokenizer_config_file: fs::read(tokenizer_dir.join("tokenizer_config.json"))
-                .map_err(|e| anyhow::anyhow!("Failed to read tokenizer_config.json: {}", e))?,
+                .map_err(|e| LocalSearchError::io("Failed to read tokenizer_config.json", e))?,
         };
 
+        let model_id = Self::local_model_id(&onnx_model_path, &onnx_file);
+
         // Create user-defined model
         let user_defined_model = UserDefinedEmbeddingModel::new(onnx_file, tokenizer_files);
 
@@ -86,14 +100,19 @@ impl LocalEmbedder {
         }
 
         // Initialize the model
-        let model = TextEmbedding::try_new_from_user_defined(user_defined_model, init_options)?;
+        let model = TextEmbedding::try_new_from_user_defined(user_defined_model, init_options)
+            .map_err(|e| LocalSearchError::embedding("Failed to load embedding model", e))?;
 
         info!(
             "Initialized local embedding model from {:?} with tokenizer from {:?}",
             onnx_model_path, tokenizer_dir
         );
 
-        Ok(LocalEmbedder { model })
+        Ok(LocalEmbedder {
+            model,
+            model_id,
+            dimension: OnceLock::new(),
+        })
     }
 
     /// Creates a new embedder with local model files using individual file paths.
@@ -115,45 +134,51 @@ impl LocalEmbedder {
     ) -> Result<Self> {
         // Load ONNX model file
         let onnx_file = fs::read(&onnx_model_path).map_err(|e| {
-            anyhow::anyhow!(
-                "Failed to read ONNX model from {:?}: {}",
-                onnx_model_path,
-                e
+            LocalSearchError::io(
+                format!("Failed to read ONNX model from {:?}", onnx_model_path),
+                e,
             )
         })?;
 
         // Load tokenizer files
         let tokenizer_files = TokenizerFiles {
             tokenizer_file: fs::read(&tokenizer_json_path).map_err(|e| {
-                anyhow::anyhow!(
-                    "Failed to read tokenizer.json from {:?}: {}",
-                    tokenizer_json_path,
-                    e
+                LocalSearchError::io(
+                    format!(
+                        "Failed to read tokenizer.json from {:?}",
+                        tokenizer_json_path
+                    ),
+                    e,
                 )
             })?,
             config_file: fs::read(&config_json_path).map_err(|e| {
-                anyhow::anyhow!(
-                    "Failed to read config.json from {:?}: {}",
-                    config_json_path,
-                    e
+                LocalSearchError::io(
+                    format!("Failed to read config.json from {:?}", config_json_path),
+                    e,
                 )
             })?,
             special_tokens_map_file: fs::read(&special_tokens_map_path).map_err(|e| {
-                anyhow::anyhow!(
-                    "Failed to read special_tokens_map.json from {:?}: {}",
-                    special_tokens_map_path,
-                    e
+                LocalSearchError::io(
+                    format!(
+                        "Failed to read special_tokens_map.json from {:?}",
+                        special_tokens_map_path
+                    ),
+                    e,
                 )
             })?,
             tokenizer_config_file: fs::read(&tokenizer_config_path).map_err(|e| {
-                anyhow::anyhow!(
-                    "Failed to read tokenizer_config.json from {:?}: {}",
-                    tokenizer_config_path,
-                    e
+                LocalSearchError::io(
+                    format!(
+                        "Failed to read tokenizer_config.json from {:?}",
+                        tokenizer_config_path
+                    ),
+                    e,
                 )
             })?,
         };
 
+        let model_id = Self::local_model_id(&onnx_model_path, &onnx_file);
+
         // Create user-defined model
         let user_defined_model = UserDefinedEmbeddingModel::new(onnx_file, tokenizer_files);
 
@@ -164,11 +189,16 @@ impl LocalEmbedder {
         }
 
         // Initialize the model
-        let model = TextEmbedding::try_new_from_user_defined(user_defined_model, init_options)?;
+        let model = TextEmbedding::try_new_from_user_defined(user_defined_model, init_options)
+            .map_err(|e| LocalSearchError::embedding("Failed to load embedding model", e))?;
 
         info!("Initialized local embedding model from individual files");
 
-        Ok(LocalEmbedder { model })
+        Ok(LocalEmbedder {
+            model,
+            model_id,
+            dimension: OnceLock::new(),
+        })
     }
 
     /// Creates a new embedder with the default model and default cache directory.
@@ -181,19 +211,55 @@ impl LocalEmbedder {
         Self::new(None, Some(cache_dir))
     }
 
+    /// Returns an identifier of the embedding model, e.g. `AllMiniLML6V2` for a pre-built
+    /// model or `local:model.onnx:<size in bytes>` for a local ONNX model. Embeddings are
+    /// only comparable between embedders with the same identifier.
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Returns the length of the vectors this embedder produces. Local models are
+    /// probed with one embedding the first time.
+    pub fn dimension(&self) -> Result<usize> {
+        if let Some(dimension) = self.dimension.get() {
+            return Ok(*dimension);
+        }
+        let dimension = self.embed_text("dimension")?.len();
+        Ok(*self.dimension.get_or_init(|| dimension))
+    }
+
+    fn local_model_id(onnx_model_path: &std::path::Path, onnx_file: &[u8]) -> String {
+        let file_name = onnx_model_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("local:{}:{}", file_name, onnx_file.len())
+    }
+
     /// Embeds a single text string and returns a normalized vector.
     pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
-        let embeddings = self.model.embed(vec![text], None)?;
+        let embeddings = self
+            .model
+            .embed(vec![text], None)
+            .map_err(|e| LocalSearchError::embedding("Failed to embed text", e))?;
         embeddings
             .into_iter()
             .next()
             .map(|x| Self::normalize_l2(&x))
-            .ok_or_else(|| anyhow::anyhow!("Failed to get embedding"))
+            .ok_or_else(|| {
+                LocalSearchError::embedding(
+                    "Failed to embed text",
+                    anyhow::anyhow!("model returned no embedding"),
+                )
+            })
     }
 
     /// Embeds multiple text strings and returns normalized vectors.
     pub fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
-        let embeddings = self.model.embed(texts, None)?;
+        let embeddings = self
+            .model
+            .embed(texts, None)
+            .map_err(|e| LocalSearchError::embedding("Failed to embed texts", e))?;
         Ok(embeddings.iter().map(|e| Self::normalize_l2(e)).collect())
     }
 
@@ -235,6 +301,7 @@ mod tests {
 
         let embedding = result.unwrap();
         assert!(!embedding.is_empty());
+        assert_eq!(embedder.dimension().unwrap(), embedding.len());
     }
 
     #[test]
@@ -1,3 +1,6 @@
 pub mod sqlite;
 
-pub use sqlite::SqliteLocalSearchEngine;
+pub use sqlite::{
+    CollectionInfo, DEFAULT_COLLECTION, MetadataField, SearchFields, SharedSqliteEngine,
+    SqliteLocalSearchEngine, Synonyms, Tokenizer, TokenizerKind,
+};
@@ -1,72 +1,394 @@
-use crate::traits::{DocumentIndexer, DocumentRequest, LocalSearch, SearchType};
+use crate::error::{LocalSearchError, Result};
+use crate::export::{
+    ExportHeader, ExportOptions, ExportReader, ExportWriter, ExportedDocument, FORMAT_VERSION,
+    ImportSummary,
+};
+use crate::query::{self, QueryNode};
+use crate::traits::{
+    DocumentIndexer, DocumentInfo, DocumentRequest, IndexStats, ListOptions, LocalSearch,
+    QueryExplanation, ScoreExplanation, SearchOptions, SearchOutcome, SearchType, StoredDocument,
+};
 use crate::{LocalEmbedder, traits::SearchResult};
-use anyhow::anyhow;
-use log::{debug, info};
-use rusqlite::Connection;
+use log::{debug, info, warn};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod diversity;
+
+mod feedback;
+
+mod fields;
+pub use fields::{MetadataField, SearchFields};
+
+mod fuzzy;
+
+mod shared;
+pub use shared::SharedSqliteEngine;
+
+mod similar;
+
+mod synonyms;
+pub use synonyms::Synonyms;
+
+mod tokenizer;
+pub use tokenizer::{Tokenizer, TokenizerKind};
 
 // Type alias for the complex embedding row tuple
 type EmbeddingRow = (String, Option<HashMap<String, String>>, f64, f64, Vec<u8>);
 
+/// `index_meta` key of the model identifier used for stored embeddings.
+const EMBEDDING_MODEL_KEY: &str = "embedding_model";
+/// `index_meta` key of the [`SearchFields`] of the full-text index.
+const SEARCH_FIELDS_KEY: &str = "search_fields";
+/// `index_meta` key of the [`Tokenizer`] of the full-text index.
+const TOKENIZER_KEY: &str = "fts_tokenizer";
+/// Key in `index_meta` of the synonym groups applied to full-text queries.
+const SYNONYMS_KEY: &str = "query_synonyms";
+
+/// Pages copied per online backup step; the source is unlocked between steps.
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+/// Pause between online backup steps so writers can make progress.
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(10);
+/// Weights of the normalized full-text score and the cosine similarity in hybrid scores.
+const HYBRID_FTS_WEIGHT: f64 = 0.6;
+const HYBRID_SEMANTIC_WEIGHT: f64 = 0.4;
+
+/// Name of the collection stored in the original, unprefixed tables.
+pub const DEFAULT_COLLECTION: &str = "default";
+
+/// Table names and metadata keys of one collection.
+#[derive(Debug, Clone)]
+struct Collection {
+    name: String,
+    documents: String,
+    fts: String,
+    embeddings: String,
+    model_key: String,
+    fields_key: String,
+    tokenizer_key: String,
+    synonyms_key: String,
+}
+
+impl Collection {
+    fn new(name: &str) -> Result<Self> {
+        if name == DEFAULT_COLLECTION {
+            return Ok(Collection {
+                name: name.to_string(),
+                documents: "documents".to_string(),
+                fts: "documents_fts".to_string(),
+                embeddings: "document_embeddings".to_string(),
+                model_key: EMBEDDING_MODEL_KEY.to_string(),
+                fields_key: SEARCH_FIELDS_KEY.to_string(),
+                tokenizer_key: TOKENIZER_KEY.to_string(),
+                synonyms_key: SYNONYMS_KEY.to_string(),
+            });
+        }
+        // Names end up in table names, which SQLite compares case-insensitively
+        let valid = name.len() <= 64
+            && name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "Invalid collection name '{}'. Use lowercase letters, digits and '_', starting with a letter.",
+                name
+            )));
+        }
+        Ok(Collection {
+            name: name.to_string(),
+            documents: format!("c_{}_documents", name),
+            fts: format!("c_{}_documents_fts", name),
+            embeddings: format!("c_{}_document_embeddings", name),
+            model_key: format!("{}:{}", EMBEDDING_MODEL_KEY, name),
+            fields_key: format!("{}:{}", SEARCH_FIELDS_KEY, name),
+            tokenizer_key: format!("{}:{}", TOKENIZER_KEY, name),
+            synonyms_key: format!("{}:{}", SYNONYMS_KEY, name),
+        })
+    }
+}
+
+/// Summary of a collection returned by [`SqliteLocalSearchEngine::list_collections`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub document_count: usize,
+    /// Model that embedded the collection's documents, if recorded.
+    pub embedding_model: Option<String>,
+    /// Creation time; unknown for the default collection.
+    pub created_at: Option<f64>,
+}
+
 pub struct SqliteLocalSearchEngine {
     db_path: String,
     conn: Connection,
-    embedder: Option<LocalEmbedder>,
+    embedder: Option<Arc<LocalEmbedder>>,
+    snapshot: bool,
+    collection: Collection,
+    /// Tokenizer for full-text indexes this engine creates.
+    tokenizer: Option<Tokenizer>,
 }
 
 impl SqliteLocalSearchEngine {
     /// Creates a new SQLite-based search engine instance with the specified database path and embedder
-    pub fn new(db_path: &str, embedder: Option<LocalEmbedder>) -> anyhow::Result<Self> {
+    pub fn new(db_path: &str, embedder: Option<LocalEmbedder>) -> Result<Self> {
+        Self::new_with_shared_embedder(db_path, embedder.map(Arc::new))
+    }
+
+    /// Creates a search engine that shares an already loaded embedder, so several
+    /// engines (or threads) can use one model.
+    pub fn new_with_shared_embedder(
+        db_path: &str,
+        embedder: Option<Arc<LocalEmbedder>>,
+    ) -> Result<Self> {
         info!("Creating new SqliteLocalSearch for path: {}", db_path);
-        let conn =
-            Connection::open(db_path).map_err(|e| anyhow!("Failed to open database: {}", e))?;
+        let conn = Connection::open(db_path)
+            .map_err(|e| LocalSearchError::sqlite("Failed to open database", e))?;
         let lfts = SqliteLocalSearchEngine {
             db_path: db_path.to_string(),
             conn,
             embedder,
+            snapshot: false,
+            collection: Collection::new(DEFAULT_COLLECTION)?,
+            tokenizer: None,
         };
         info!("SqliteLocalSearch initialization complete: {}", db_path);
         Ok(lfts)
     }
 
-    /// Creates the required database tables for documents, FTS index, and embeddings.
-    pub fn create_table(&self) -> anyhow::Result<()> {
+    /// Opens a read-only snapshot of the database at `db_path`.
+    ///
+    /// Searches on the snapshot keep seeing the index as it was when it was opened,
+    /// even while another process writes to it; [`refresh`](DocumentIndexer::refresh)
+    /// moves the snapshot forward. Writes through a snapshot fail.
+    ///
+    /// Databases in WAL mode are read in place; others are copied into memory so the
+    /// snapshot does not block writers.
+    pub fn open_snapshot(db_path: &str, embedder: Option<LocalEmbedder>) -> Result<Self> {
+        info!("Opening read-only snapshot of: {}", db_path);
+        let conn = Self::open_snapshot_connection(db_path)?;
+        Ok(SqliteLocalSearchEngine {
+            db_path: db_path.to_string(),
+            conn,
+            embedder: embedder.map(Arc::new),
+            snapshot: true,
+            collection: Collection::new(DEFAULT_COLLECTION)?,
+            tokenizer: None,
+        })
+    }
+
+    /// Scopes the engine to a named collection; documents, searches, stats and exports
+    /// then use that collection only. Call [`create_table`](Self::create_table) to
+    /// create it if needed.
+    pub fn with_collection(mut self, name: &str) -> Result<Self> {
+        self.collection = Collection::new(name)?;
+        Ok(self)
+    }
+
+    /// Sets the tokenizer of full-text indexes created by [`create_table`](Self::create_table)
+    /// and [`create_collection`](Self::create_collection). Existing indexes keep the
+    /// tokenizer they were created with; see [`set_tokenizer`](Self::set_tokenizer).
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Result<Self> {
+        tokenizer.validate()?;
+        self.tokenizer = Some(tokenizer);
+        Ok(self)
+    }
+
+    /// Returns the name of the collection this engine works on.
+    pub fn collection(&self) -> &str {
+        &self.collection.name
+    }
+
+    /// Turns the engine into a [`SharedSqliteEngine`] that can be used from many
+    /// threads at once, with one reader connection per available CPU.
+    pub fn into_shared(self) -> Result<SharedSqliteEngine> {
+        let max_readers = std::thread::available_parallelism().map_or(4, |n| n.get());
+        SharedSqliteEngine::new(self, max_readers)
+    }
+
+    /// Returns true if this engine is a read-only snapshot.
+    pub fn is_snapshot(&self) -> bool {
+        self.snapshot
+    }
+
+    fn open_snapshot_connection(db_path: &str) -> Result<Connection> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|e| LocalSearchError::sqlite("Failed to open database snapshot", e))?;
+        let journal_mode: String = conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .map_err(|e| LocalSearchError::sqlite("Failed to read journal mode", e))?;
+
+        // In WAL mode a long read transaction pins the snapshot without blocking
+        // writers; otherwise it would, so take an in-memory copy instead.
+        let conn = if journal_mode.eq_ignore_ascii_case("wal") {
+            conn
+        } else {
+            debug!("Database is not in WAL mode, copying snapshot into memory");
+            let mut copy = Connection::open_in_memory()
+                .map_err(|e| LocalSearchError::sqlite("Failed to open in-memory database", e))?;
+            // One step holds the read lock for the whole copy, keeping it consistent
+            Backup::new(&conn, &mut copy)
+                .and_then(|backup| backup.run_to_completion(i32::MAX, BACKUP_STEP_PAUSE, None))
+                .map_err(|e| LocalSearchError::sqlite("Failed to copy database snapshot", e))?;
+            copy
+        };
+
+        conn.execute_batch("PRAGMA query_only = ON; BEGIN DEFERRED;")
+            .map_err(|e| LocalSearchError::sqlite("Failed to start snapshot transaction", e))?;
+        // The read transaction, and with it the snapshot, starts at the first read
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(|e| LocalSearchError::sqlite("Failed to start snapshot transaction", e))?;
+        Ok(conn)
+    }
+
+    /// Copies the database to `path` with SQLite's online backup API.
+    ///
+    /// The source stays usable by other connections while the copy runs, and the
+    /// backup is a consistent image of the index. The copy is written next to
+    /// `path` first and renamed into place once complete.
+    pub fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if path == Path::new(&self.db_path) {
+            return Err(LocalSearchError::InvalidArgument(
+                "Backup destination is the database itself".to_string(),
+            ));
+        }
+        let mut partial_path = path.as_os_str().to_owned();
+        partial_path.push(".partial");
+        let partial_path = std::path::PathBuf::from(partial_path);
+        if partial_path.exists() {
+            std::fs::remove_file(&partial_path)
+                .map_err(|e| LocalSearchError::io("Failed to remove stale backup file", e))?;
+        }
+
+        let mut destination = Connection::open(&partial_path)
+            .map_err(|e| LocalSearchError::sqlite("Failed to open backup destination", e))?;
+        Backup::new(&self.conn, &mut destination)
+            .and_then(|backup| {
+                backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+            })
+            .map_err(|e| LocalSearchError::sqlite("Failed to back up database", e))?;
+        destination
+            .close()
+            .map_err(|e| LocalSearchError::sqlite("Failed to close backup destination", e.1))?;
+
+        std::fs::rename(&partial_path, path)
+            .map_err(|e| LocalSearchError::io("Failed to move backup into place", e))?;
+        info!("Backed up {} to {}", self.db_path, path.display());
+        Ok(())
+    }
+
+    /// Creates the required database tables for documents, FTS index, and embeddings
+    /// of the engine's collection. Existing tables and their contents are kept.
+    pub fn create_table(&self) -> Result<()> {
+        self.create_collection_tables(&self.collection)
+    }
+
+    fn create_collection_tables(&self, collection: &Collection) -> Result<()> {
+        let Collection {
+            documents,
+            fts,
+            embeddings,
+            ..
+        } = collection;
         self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS documents (
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (
                     path TEXT PRIMARY KEY,
                     content TEXT NOT NULL,
                     metadata TEXT NOT NULL,
                     createdAt REAL NOT NULL,
                     updatedAt REAL NOT NULL
                 )",
+                documents
+            ),
             [],
         )?;
-        debug!("Created documents table if it did not exist.");
-
-        self.conn
-            .execute("DROP TABLE IF EXISTS documents_fts", [])?;
+        debug!("Created {} table if it did not exist.", documents);
 
-        debug!("Dropped existing documents_fts table if it existed.");
+        // Create embeddings table only if embedder is available
         self.conn.execute(
-            "CREATE VIRTUAL TABLE documents_fts USING fts5(
-                path UNINDEXED,
-                content,
-                tokenize = 'porter ascii'
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                path TEXT PRIMARY KEY,
+                embedding BLOB NOT NULL,
+                FOREIGN KEY(path) REFERENCES {}(path)
             )",
+                embeddings, documents
+            ),
             [],
         )?;
-        debug!("Created documents_fts FTS5 virtual table.");
+        debug!("Created {} table if it did not exist.", embeddings);
 
-        // Create embeddings table only if embedder is available
         self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS document_embeddings (
-                path TEXT PRIMARY KEY,
-                embedding BLOB NOT NULL,
-                FOREIGN KEY(path) REFERENCES documents(path)
+            "CREATE TABLE IF NOT EXISTS index_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
             )",
             [],
         )?;
-        debug!("Created document_embeddings table if it did not exist.");
+        debug!("Created index_meta table if it did not exist.");
+
+        if collection.name != DEFAULT_COLLECTION {
+            self.conn.execute(
+                "CREATE TABLE IF NOT EXISTS collections (
+                    name TEXT PRIMARY KEY,
+                    createdAt REAL NOT NULL
+                )",
+                [],
+            )?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64();
+            self.conn.execute(
+                "INSERT OR IGNORE INTO collections (name, createdAt) VALUES (?1, ?2)",
+                rusqlite::params![collection.name, now],
+            )?;
+        }
+
+        // Record which model embeds documents so stats and imports can check it. An
+        // existing record is kept, as it describes the embeddings already stored.
+        if let Some(embedder) = &self.embedder {
+            match self.get_meta(&collection.model_key)? {
+                None => self.set_meta(&collection.model_key, embedder.model_id())?,
+                Some(model) if model != embedder.model_id() => warn!(
+                    "Collection '{}' is embedded with {}, not {}; semantic search skips it",
+                    collection.name,
+                    model,
+                    embedder.model_id()
+                ),
+                Some(_) => {}
+            }
+        }
+
+        let stored_tokenizer = self.tokenizer_of(collection)?;
+        if self.table_exists(fts)? {
+            if let Some(tokenizer) = self.tokenizer.filter(|t| *t != stored_tokenizer) {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Collection '{}' is indexed with tokenizer '{}', not '{}'; use set_tokenizer to change it",
+                    collection.name, stored_tokenizer, tokenizer
+                )));
+            }
+            debug!("Keeping existing {} FTS5 virtual table.", fts);
+            // Indexes created before typo tolerance have no vocabulary table yet
+            self.create_vocabulary_table(collection)?;
+        } else {
+            let tokenizer = self.tokenizer.unwrap_or(stored_tokenizer);
+            self.set_meta(&collection.tokenizer_key, &tokenizer.to_string())?;
+            self.rebuild_fts(collection, &self.fields_of(collection)?, tokenizer)?;
+        }
 
         // let schema: String = self.conn.query_one("SELECT sql FROM sqlite_main WHERE type='table' AND name='documents'", [], |row| row.get(0))?;
         // debug!("Documents table schema: {}", schema);
@@ -80,27 +402,402 @@ impl SqliteLocalSearchEngine {
         Ok(())
     }
 
+    /// Recreates a collection's full-text index and fills it from the stored documents.
+    /// Returns the number of indexed documents.
+    fn rebuild_fts(
+        &self,
+        collection: &Collection,
+        fields: &SearchFields,
+        tokenizer: Tokenizer,
+    ) -> Result<usize> {
+        let Collection { documents, fts, .. } = collection;
+        self.conn
+            .execute(&format!("DROP TABLE IF EXISTS {}", fts), [])
+            .map_err(|e| LocalSearchError::sqlite("Failed to drop full-text index", e))?;
+        self.conn
+            .execute(
+                &format!(
+                    "CREATE VIRTUAL TABLE {} USING fts5({}, tokenize = '{}')",
+                    fts,
+                    fields.fts_columns(),
+                    tokenizer.to_sql()
+                ),
+                [],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to create full-text index", e))?;
+        debug!("Created {} FTS5 virtual table.", fts);
+        self.create_vocabulary_table(collection)?;
+        self.conn
+            .execute(&fields.fts_insert_sql(fts, documents, ""), [])
+            .map_err(|e| LocalSearchError::sqlite("Failed to rebuild full-text index", e))
+    }
+
+    /// Creates the `fts5vocab` tables that spelling correction reads index terms and
+    /// their positions from.
+    fn create_vocabulary_table(&self, collection: &Collection) -> Result<()> {
+        for (table, kind) in [
+            (Self::vocabulary_table(collection), "row"),
+            (Self::instances_table(collection), "instance"),
+        ] {
+            self.conn
+                .execute(
+                    &format!(
+                        "CREATE VIRTUAL TABLE IF NOT EXISTS {} USING fts5vocab({}, '{}')",
+                        table, collection.fts, kind
+                    ),
+                    [],
+                )
+                .map_err(|e| LocalSearchError::sqlite("Failed to create index vocabulary", e))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the tokenizer of the full-text index of the engine's collection.
+    pub fn tokenizer(&self) -> Result<Tokenizer> {
+        self.tokenizer_of(&self.collection)
+    }
+
+    /// Changes the tokenizer of the engine's collection and rebuilds its full-text
+    /// index from the stored documents.
+    pub fn set_tokenizer(&self, tokenizer: Tokenizer) -> Result<()> {
+        tokenizer.validate()?;
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        self.set_meta(&self.collection.tokenizer_key, &tokenizer.to_string())?;
+        let fields = self.fields_of(&self.collection)?;
+        let rows = self.rebuild_fts(&self.collection, &fields, tokenizer)?;
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit tokenizer", e))?;
+        info!(
+            "Reindexed {} documents in collection '{}' with tokenizer '{}'",
+            rows, self.collection.name, tokenizer
+        );
+        Ok(())
+    }
+
+    /// Reads a collection's tokenizer; indexes without one recorded predate the
+    /// setting and use the default.
+    fn tokenizer_of(&self, collection: &Collection) -> Result<Tokenizer> {
+        match self.get_meta(&collection.tokenizer_key)? {
+            Some(value) => value.parse().map_err(|e| {
+                LocalSearchError::InvalidData(format!("Invalid stored tokenizer: {}", e))
+            }),
+            None => Ok(Tokenizer::default()),
+        }
+    }
+
+    /// Returns the fields indexed by full-text search in the engine's collection.
+    pub fn search_fields(&self) -> Result<SearchFields> {
+        self.fields_of(&self.collection)
+    }
+
+    /// Changes the fields indexed by full-text search in the engine's collection and
+    /// rebuilds its full-text index from the stored documents.
+    pub fn set_search_fields(&self, fields: &SearchFields) -> Result<()> {
+        fields.validate()?;
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        let value = serde_json::to_string(fields).map_err(|e| {
+            LocalSearchError::InvalidData(format!("Failed to serialize search fields: {}", e))
+        })?;
+        self.set_meta(&self.collection.fields_key, &value)?;
+        let tokenizer = self.tokenizer_of(&self.collection)?;
+        let rows = self.rebuild_fts(&self.collection, fields, tokenizer)?;
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit search fields", e))?;
+        info!(
+            "Indexed fields {:?} of {} documents in collection '{}'",
+            fields.names(),
+            rows,
+            self.collection.name
+        );
+        Ok(())
+    }
+
+    /// Reads a collection's search fields; indexes without any recorded use the
+    /// default, content-only fields.
+    fn fields_of(&self, collection: &Collection) -> Result<SearchFields> {
+        match self.get_meta(&collection.fields_key)? {
+            Some(value) => serde_json::from_str(&value).map_err(|e| {
+                LocalSearchError::InvalidData(format!("Invalid search fields: {}", e))
+            }),
+            None => Ok(SearchFields::default()),
+        }
+    }
+
+    /// Returns the synonym groups applied to full-text queries in the engine's collection.
+    pub fn synonyms(&self) -> Result<Synonyms> {
+        self.synonyms_of(&self.collection)
+    }
+
+    /// Replaces the synonym groups of the engine's collection. They apply to the next
+    /// search; the index is unchanged.
+    pub fn set_synonyms(&self, synonyms: &Synonyms) -> Result<()> {
+        synonyms.validate()?;
+        let value = serde_json::to_string(synonyms).map_err(|e| {
+            LocalSearchError::InvalidData(format!("Failed to serialize synonyms: {}", e))
+        })?;
+        self.set_meta(&self.collection.synonyms_key, &value)?;
+        info!(
+            "Set {} synonym groups in collection '{}'",
+            synonyms.groups.len(),
+            self.collection.name
+        );
+        Ok(())
+    }
+
+    fn synonyms_of(&self, collection: &Collection) -> Result<Synonyms> {
+        match self.get_meta(&collection.synonyms_key)? {
+            Some(value) => serde_json::from_str(&value)
+                .map_err(|e| LocalSearchError::InvalidData(format!("Invalid synonyms: {}", e))),
+            None => Ok(Synonyms::default()),
+        }
+    }
+
+    /// Adds the stored document at `path` to the full-text index.
+    fn insert_fts_entry(&self, path: &str) -> Result<()> {
+        let fields = self.fields_of(&self.collection)?;
+        self.conn
+            .execute(
+                &fields.fts_insert_sql(
+                    &self.collection.fts,
+                    &self.collection.documents,
+                    " WHERE d.path = ?1",
+                ),
+                rusqlite::params![path],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to insert into FTS", e))?;
+        Ok(())
+    }
+
+    /// Creates an empty named collection, recording the engine's embedding model for it.
+    pub fn create_collection(&self, name: &str) -> Result<()> {
+        if self.collection_exists(name)? {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "Collection '{}' already exists",
+                name
+            )));
+        }
+        let collection = Collection::new(name)?;
+        self.create_collection_tables(&collection)?;
+        info!("Created collection: {}", name);
+        Ok(())
+    }
+
+    /// Returns true if the collection's tables exist.
+    pub fn collection_exists(&self, name: &str) -> Result<bool> {
+        self.table_exists(&Collection::new(name)?.documents)
+    }
+
+    /// Lists the collections in the database, the default collection first.
+    pub fn list_collections(&self) -> Result<Vec<CollectionInfo>> {
+        let mut names: Vec<(String, Option<f64>)> = Vec::new();
+        if self.collection_exists(DEFAULT_COLLECTION)? {
+            names.push((DEFAULT_COLLECTION.to_string(), None));
+        }
+        if self.table_exists("collections")? {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT name, createdAt FROM collections ORDER BY name")
+                .map_err(|e| LocalSearchError::sqlite("Failed to list collections", e))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, Some(row.get(1)?))))
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                .map_err(|e| LocalSearchError::sqlite("Failed to list collections", e))?;
+            names.extend(rows);
+        }
+
+        names
+            .into_iter()
+            .map(|(name, created_at)| {
+                let collection = Collection::new(&name)?;
+                let document_count: i64 = self
+                    .conn
+                    .query_row(
+                        &format!("SELECT COUNT(*) FROM {}", collection.documents),
+                        [],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| {
+                        LocalSearchError::sqlite(
+                            format!("Failed to count documents in '{}'", name),
+                            e,
+                        )
+                    })?;
+                Ok(CollectionInfo {
+                    embedding_model: self.get_meta(&collection.model_key)?,
+                    name,
+                    document_count: document_count as usize,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Drops a named collection with all its documents and embeddings.
+    pub fn drop_collection(&self, name: &str) -> Result<()> {
+        if name == DEFAULT_COLLECTION {
+            return Err(LocalSearchError::InvalidArgument(
+                "The default collection cannot be dropped; delete its documents instead"
+                    .to_string(),
+            ));
+        }
+        if !self.collection_exists(name)? {
+            return Err(LocalSearchError::NotFound(format!("Collection '{}'", name)));
+        }
+        let collection = Collection::new(name)?;
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        for table in [
+            &collection.embeddings,
+            &Self::vocabulary_table(&collection),
+            &Self::instances_table(&collection),
+            &collection.fts,
+            &collection.documents,
+        ] {
+            tx.execute(&format!("DROP TABLE IF EXISTS {}", table), [])
+                .map_err(|e| LocalSearchError::sqlite(format!("Failed to drop {}", table), e))?;
+        }
+        tx.execute(
+            "DELETE FROM collections WHERE name = ?1",
+            rusqlite::params![name],
+        )
+        .map_err(|e| LocalSearchError::sqlite("Failed to unregister collection", e))?;
+        tx.execute(
+            "DELETE FROM index_meta WHERE key IN (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                collection.model_key,
+                collection.fields_key,
+                collection.tokenizer_key,
+                collection.synonyms_key
+            ],
+        )
+        .map_err(|e| LocalSearchError::sqlite("Failed to remove collection metadata", e))?;
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit drop", e))?;
+        info!("Dropped collection: {}", name);
+        Ok(())
+    }
+
+    /// Resolves the collections a search runs over; `"*"` means all of them.
+    fn search_collections(&self, options: &SearchOptions) -> Result<Vec<Collection>> {
+        let Some(names) = &options.collections else {
+            return Ok(vec![self.collection.clone()]);
+        };
+        if names.iter().any(|name| name == "*") {
+            return self
+                .list_collections()?
+                .iter()
+                .map(|info| Collection::new(&info.name))
+                .collect();
+        }
+        names
+            .iter()
+            .map(|name| {
+                if !self.collection_exists(name)? {
+                    return Err(LocalSearchError::NotFound(format!("Collection '{}'", name)));
+                }
+                Collection::new(name)
+            })
+            .collect()
+    }
+
+    /// Returns false if the collection's documents were embedded by a different model
+    /// than the engine's embedder, in which case their vectors are not comparable.
+    fn embedder_matches(&self, collection: &Collection) -> Result<bool> {
+        let Some(embedder) = &self.embedder else {
+            return Ok(false);
+        };
+        match self.get_meta(&collection.model_key)? {
+            Some(model) if model != embedder.model_id() => {
+                info!(
+                    "Skipping semantic search in collection '{}' embedded with {}",
+                    collection.name, model
+                );
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    fn table_exists(&self, name: &str) -> Result<bool> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                rusqlite::params![name],
+                |row| row.get(0),
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to read database schema", e))
+    }
+
+    /// Reads a value from the `index_meta` table; databases created before the table
+    /// existed have no metadata.
+    fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        if !self.table_exists("index_meta")? {
+            return Ok(None);
+        }
+        self.conn
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = ?1",
+                rusqlite::params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to read index metadata", e))
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO index_meta (key, value) VALUES (?1, ?2)",
+                rusqlite::params![key, value],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to write index metadata", e))?;
+        Ok(())
+    }
+
     fn search_semantic_only(
         &self,
-        query: &str,
-        path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        let embedder = self
-            .embedder
-            .as_ref()
-            .ok_or_else(|| anyhow!("Semantic search requires an embedder"))?;
-        let query_embedding = embedder.embed_text(query)?;
-        let semantic_results = self.search_by_embedding(&query_embedding, path_filters)?;
+        collection: &Collection,
+        query_embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if !self.embedder_matches(collection)? {
+            return Ok(Vec::new());
+        }
+        self.semantic_ranking(collection, query_embedding, options)
+    }
+
+    /// Ranks documents by cosine similarity alone.
+    fn semantic_ranking(
+        &self,
+        collection: &Collection,
+        query_embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let semantic_results = self.search_by_embedding(collection, query_embedding, options)?;
         let results = semantic_results
             .into_iter()
             .map(|r| SearchResult {
                 path: r.path,
+                collection: r.collection,
                 metadata: r.metadata,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
                 fts_score: None,
                 semantic_score: Some(r.semantic_score.unwrap_or(0.0)),
                 final_score: r.final_score,
+                explanation: r.explanation.map(|explanation| ScoreExplanation {
+                    semantic_weight: 1.0,
+                    ..explanation
+                }),
             })
             .collect();
         Ok(results)
@@ -108,22 +805,39 @@ impl SqliteLocalSearchEngine {
 
     fn search_hybrid(
         &self,
-        query: &str,
-        path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        // If no embedder, fallback to FTS-only search
-        if self.embedder.is_none() {
-            debug!("No embedder available for hybrid search, falling back to FTS-only");
-            return self.search_fulltext_only(query, path_filters);
-        }
+        collection: &Collection,
+        fts_query: Option<&str>,
+        query_embedding: Option<&[f32]>,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        // If no embedder (or the collection was embedded by another model), fallback to FTS-only search
+        let query_embedding = match query_embedding {
+            Some(embedding) if self.embedder_matches(collection)? => embedding,
+            _ => {
+                debug!("No embedder available for hybrid search, falling back to FTS-only");
+                return self.search_fulltext_only(collection, fts_query, options);
+            }
+        };
+        self.hybrid_ranking(collection, fts_query, query_embedding, options)
+    }
 
-        // Get FTS results
-        let fts_results = self.search_fts(query, path_filters).unwrap_or_default();
+    /// Blends the normalized full-text scores with cosine similarities.
+    fn hybrid_ranking(
+        &self,
+        collection: &Collection,
+        fts_query: Option<&str>,
+        query_embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        // Get FTS results; the query was validated, so errors are real failures
+        let fts_results = match fts_query {
+            Some(fts_query) => self.search_fts(collection, fts_query, options)?,
+            None => Vec::new(),
+        };
 
         // Get semantic results
-        let query_embedding = self.embedder.as_ref().unwrap().embed_text(query)?;
         let semantic_results = self
-            .search_by_embedding(&query_embedding, path_filters)
+            .search_by_embedding(collection, query_embedding, options)
             .unwrap_or_default();
 
         // Combine and normalize scores
@@ -151,8 +865,14 @@ impl SqliteLocalSearchEngine {
         for result in semantic_results {
             let result_score = result.semantic_score.unwrap_or(0.0); // Extract score before move
             match combined_results.get_mut(&result.path) {
-                Some((_, _fts_score, semantic_score)) => {
+                Some((base_result, _fts_score, semantic_score)) => {
                     *semantic_score = Some(result_score);
+                    if let (Some(explanation), Some(semantic)) =
+                        (base_result.explanation.as_mut(), result.explanation)
+                    {
+                        explanation.cosine_similarity = semantic.cosine_similarity;
+                        explanation.semantic_rank = semantic.semantic_rank;
+                    }
                 }
                 None => {
                     combined_results
@@ -165,18 +885,26 @@ impl SqliteLocalSearchEngine {
         let mut final_results: Vec<SearchResult> = combined_results
             .into_iter()
             .map(|(_, (base_result, fts_score, semantic_score))| {
-                let fts_component = fts_score.unwrap_or(0.0) * 0.6;
-                let semantic_component = semantic_score.unwrap_or(0.0) * 0.4;
+                let fts_component = fts_score.unwrap_or(0.0) * HYBRID_FTS_WEIGHT;
+                let semantic_component = semantic_score.unwrap_or(0.0) * HYBRID_SEMANTIC_WEIGHT;
                 let final_score = fts_component + semantic_component;
+                let explanation = base_result.explanation.map(|explanation| ScoreExplanation {
+                    fts_normalized: fts_score,
+                    fts_weight: HYBRID_FTS_WEIGHT,
+                    semantic_weight: HYBRID_SEMANTIC_WEIGHT,
+                    ..explanation
+                });
 
                 SearchResult {
                     path: base_result.path,
+                    collection: base_result.collection,
                     metadata: base_result.metadata.clone(),
                     created_at: base_result.created_at,
                     updated_at: base_result.updated_at,
                     fts_score,
                     semantic_score,
                     final_score,
+                    explanation,
                 }
             })
             .collect();
@@ -190,7 +918,7 @@ impl SqliteLocalSearchEngine {
 
         debug!(
             "Hybrid search for query '{}' returned {} results.",
-            query,
+            fts_query.unwrap_or_default(),
             final_results.len()
         );
         Ok(final_results)
@@ -198,40 +926,28 @@ impl SqliteLocalSearchEngine {
 
     fn search_by_embedding(
         &self,
+        collection: &Collection,
         query_embedding: &[f32],
-        path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        let (sql, params): (String, Vec<String>) =
-            if let Some(filters) = path_filters.filter(|f| !f.is_empty()) {
-                let like_conditions = filters
-                    .iter()
-                    .map(|_| "d.path LIKE '%' || ? || '%'")
-                    .collect::<Vec<_>>()
-                    .join(" OR ");
-                (
-                    format!(
-                        "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, e.embedding
-                     FROM documents d 
-                     JOIN document_embeddings e ON d.path = e.path
-                     WHERE {}",
-                        like_conditions
-                    ),
-                    filters.to_vec(),
-                )
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let (conditions, params) = Self::filter_conditions(options);
+        let sql = format!(
+            "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, e.embedding
+             FROM {} d
+             JOIN {} e ON d.path = e.path{}",
+            collection.documents,
+            collection.embeddings,
+            if conditions.is_empty() {
+                String::new()
             } else {
-                (
-                    "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, e.embedding
-                 FROM documents d 
-                 JOIN document_embeddings e ON d.path = e.path"
-                        .to_string(),
-                    vec![],
-                )
-            };
+                format!(" WHERE {}", conditions.join(" AND "))
+            }
+        );
 
         let mut stmt = self
             .conn
             .prepare(&sql)
-            .map_err(|e| anyhow!("Failed to prepare semantic search query: {}", e))?;
+            .map_err(|e| LocalSearchError::sqlite("Failed to prepare semantic search query", e))?;
 
         let row_mapper = |row: &rusqlite::Row<'_>| -> rusqlite::Result<EmbeddingRow> {
             let path: String = row.get(0)?;
@@ -251,18 +967,15 @@ impl SqliteLocalSearchEngine {
                 params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
             stmt.query_map(params_refs.as_slice(), row_mapper)
         }
-        .map_err(|e| anyhow!("Failed to query embeddings: {}", e))?;
+        .map_err(|e| LocalSearchError::sqlite("Failed to query embeddings", e))?;
 
         let mut results = Vec::new();
         for result in embedding_iter {
             let (path, metadata, created_at, updated_at, embedding_bytes) =
-                result.map_err(|e| anyhow!("Failed to read embedding row: {}", e))?;
+                result.map_err(|e| LocalSearchError::sqlite("Failed to read embedding row", e))?;
 
             // Convert bytes back to f32 vector
-            let embedding: Vec<f32> = embedding_bytes
-                .chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
+            let embedding = Self::embedding_from_bytes(&embedding_bytes);
 
             // Calculate cosine similarity
             let similarity = Self::cosine_similarity(query_embedding, &embedding);
@@ -272,12 +985,14 @@ impl SqliteLocalSearchEngine {
 
             results.push(SearchResult {
                 path,
+                collection: collection.name.clone(),
                 metadata,
                 created_at,
                 updated_at,
                 fts_score: None,
                 semantic_score: Some(similarity),
                 final_score: similarity,
+                explanation: None,
             });
         }
 
@@ -289,75 +1004,81 @@ impl SqliteLocalSearchEngine {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
+        if options.explain {
+            for (i, result) in results.iter_mut().enumerate() {
+                result.explanation = Some(ScoreExplanation {
+                    cosine_similarity: result.semantic_score,
+                    semantic_rank: Some(i + 1),
+                    ..ScoreExplanation::default()
+                });
+            }
+        }
+
         debug!("Semantic search returned {} results.", results.len());
         Ok(results)
     }
 
     fn search_fulltext_only(
         &self,
-        query: &str,
-        path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        let fts_results = self.search_fts(query, path_filters)?;
+        collection: &Collection,
+        fts_query: Option<&str>,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(fts_query) = fts_query else {
+            debug!("Query has no searchable words, skipping full-text search");
+            return Ok(Vec::new());
+        };
+        let fts_results = self.search_fts(collection, fts_query, options)?;
         info!(
             "Full-text search for query '{}' returned {} results.",
-            query,
+            fts_query,
             fts_results.len()
         );
         let results = fts_results
             .into_iter()
             .map(|r| SearchResult {
                 path: r.path,
+                collection: r.collection,
                 metadata: r.metadata,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
                 fts_score: Some(r.fts_score.unwrap_or(0.0)),
                 semantic_score: None,
                 final_score: r.final_score,
+                explanation: r.explanation.map(|explanation| ScoreExplanation {
+                    fts_weight: 1.0,
+                    ..explanation
+                }),
             })
             .collect();
         Ok(results)
     }
 
+    /// Runs an FTS5 match expression, as produced by [`crate::query::to_fts_query`].
     fn search_fts(
         &self,
-        query: &str,
-        path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        let (sql, params): (String, Vec<String>) = if let Some(filters) =
-            path_filters.filter(|f| !f.is_empty())
-        {
-            let like_conditions = filters
+        collection: &Collection,
+        fts_query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let (conditions, filter_params) = Self::filter_conditions(options);
+        let fields = self.fields_of(collection)?;
+        let sql = format!(
+            "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, bm25({fts}, {weights}) as score
+             FROM {fts}
+             JOIN {documents} d ON {fts}.path = d.path
+             WHERE {fts} MATCH ?1{filters}
+             ORDER BY score",
+            fts = collection.fts,
+            weights = fields.bm25_weights(),
+            documents = collection.documents,
+            filters = conditions
                 .iter()
-                .map(|_| "d.path LIKE '%' || ? || '%'")
-                .collect::<Vec<_>>()
-                .join(" OR ");
-            (
-                format!(
-                    "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, bm25(documents_fts) as score
-                     FROM documents_fts 
-                     JOIN documents d ON documents_fts.path = d.path
-                     WHERE documents_fts MATCH ?1 AND ({})
-                     ORDER BY score",
-                    like_conditions
-                ),
-                {
-                    let mut p = vec![query.to_string()];
-                    p.extend(filters.iter().cloned());
-                    p
-                }
-            )
-        } else {
-            (
-                "SELECT d.path, d.metadata, d.createdAt, d.updatedAt, bm25(documents_fts) as score
-                 FROM documents_fts 
-                 JOIN documents d ON documents_fts.path = d.path
-                 WHERE documents_fts MATCH ?1
-                 ORDER BY score"
-                    .to_string(),
-                vec![query.to_string()],
-            )
-        };
+                .map(|c| format!(" AND {}", c))
+                .collect::<String>()
+        );
+        let mut params = vec![fts_query.to_string()];
+        params.extend(filter_params);
 
         let mut stmt = self.conn.prepare(&sql)?;
 
@@ -369,12 +1090,14 @@ impl SqliteLocalSearchEngine {
             };
             Ok(SearchResult {
                 path: row.get(0)?,
+                collection: collection.name.clone(),
                 metadata: serde_json::from_str(&row.get::<_, String>(1)?).ok(),
                 created_at: row.get(2)?,
                 updated_at: row.get(3)?,
                 fts_score: Some(score),
                 semantic_score: None,
                 final_score: score,
+                explanation: None,
             })
         };
 
@@ -397,76 +1120,416 @@ impl SqliteLocalSearchEngine {
             for (i, result) in results.iter_mut().enumerate() {
                 result.fts_score = Some(normalized_scores[i]);
                 result.final_score = normalized_scores[i];
+                if options.explain {
+                    result.explanation = Some(ScoreExplanation {
+                        bm25: Some(-scores[i]),
+                        fts_softmax: Some(normalized_scores[i]),
+                        fts_rank: Some(i + 1),
+                        ..ScoreExplanation::default()
+                    });
+                }
             }
         }
 
         debug!(
             "Search for query '{}' returned {} results.",
-            query,
+            fts_query,
             results.len()
         );
         Ok(results)
     }
 
-    fn softmax(scores: &[f64]) -> Vec<f64> {
-        let max_score = scores.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        let exp_scores: Vec<f64> = scores
-            .iter()
-            .map(|&score| (score - max_score).exp())
-            .collect();
-        let sum_exp: f64 = exp_scores.iter().sum();
-        exp_scores
-            .iter()
-            .map(|&exp_score| {
-                if sum_exp > 0.0 {
-                    exp_score / sum_exp
-                } else {
-                    1.0 / scores.len() as f64
-                }
-            })
-            .collect()
+    /// Parses `query` for a collection's fields, or returns `None` if it has no
+    /// searchable words.
+    fn parse_query_for(
+        &self,
+        collection: &Collection,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Option<QueryNode>> {
+        let fields = self.fields_of(collection)?;
+        query::parse_query(query, options.query_syntax, &fields.names())
     }
 
-    fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
-        if a.len() != b.len() {
-            return 0.0;
-        }
+    /// Rewrites a parsed query into an FTS5 match expression: terms with synonyms also
+    /// match the synonyms, with [`SearchOptions::fuzzy`] words missing from the index
+    /// also match their closest index terms, and with [`SearchOptions::query_expansion`]
+    /// terms from the top semantic matches of `query_embedding` are added as
+    /// alternatives.
+    fn rewrite_query(
+        &self,
+        collection: &Collection,
+        node: Option<QueryNode>,
+        query_embedding: Option<&[f32]>,
+        options: &SearchOptions,
+    ) -> Result<QueryExplanation> {
+        let mut explanation = QueryExplanation {
+            collection: collection.name.clone(),
+            ..QueryExplanation::default()
+        };
+        let Some(mut node) = node else {
+            return Ok(explanation);
+        };
+        let mut corrections = HashMap::new();
+        if options.fuzzy {
+            for word in fuzzy::correctable_words(&node) {
+                if let Some(candidates) = self.spelling_candidates(collection, word)?
+                    && !candidates.is_empty()
+                {
+                    let words = candidates.into_iter().map(|c| c.word).collect();
+                    corrections.insert(word.to_string(), words);
+                }
+            }
+        }
+        let synonyms = self.synonyms_of(collection)?;
+        if !synonyms.is_empty() {
+            node = synonyms.expand(node, &mut explanation.synonyms);
+        }
+        node = fuzzy::expand_terms(node, &corrections);
+        explanation.corrections = corrections.into_iter().collect();
+
+        if let (Some(expansion), Some(query_embedding)) =
+            (&options.query_expansion, query_embedding)
+        {
+            let exclude = node
+                .words()
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect();
+            explanation.expansion_terms =
+                self.expansion_terms(collection, query_embedding, expansion, &exclude, options)?;
+            if !explanation.expansion_terms.is_empty() {
+                let mut alternatives = vec![node];
+                alternatives.extend(explanation.expansion_terms.iter().map(|term| {
+                    QueryNode::Term {
+                        text: term.clone(),
+                        prefix: false,
+                    }
+                }));
+                node = QueryNode::Or(alternatives);
+            }
+        }
+        explanation.fts_query = Some(node.to_fts());
+        Ok(explanation)
+    }
+
+    /// Embeds the query for a search, or returns `None` if the search has no use for
+    /// an embedding. Full-text search only needs one for query expansion.
+    fn query_embedding(
+        &self,
+        query: &str,
+        search_type: &SearchType,
+        options: &SearchOptions,
+    ) -> Result<Option<Vec<f32>>> {
+        let expand = options.query_expansion.is_some();
+        match (search_type, &self.embedder) {
+            (SearchType::FullText, _) if !expand => Ok(None),
+            (_, Some(embedder)) => Ok(Some(embedder.embed_text(query)?)),
+            (SearchType::Semantic, None) => {
+                Err(LocalSearchError::MissingEmbedder("Semantic search"))
+            }
+            (_, None) if expand => Err(LocalSearchError::MissingEmbedder("Query expansion")),
+            (_, None) => Ok(None),
+        }
+    }
+
+    /// Searches every requested collection, embedding the query once and rewriting it
+    /// once per collection. With `suggest`, the "did you mean" suggestion is collected
+    /// from the parsed query for full-text and hybrid searches.
+    fn search_outcome(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+        suggest: bool,
+    ) -> Result<SearchOutcome> {
+        let query_embedding = self.query_embedding(query, &search_type, options)?;
+        let collections = self.search_collections(options)?;
+        let mut outcome = SearchOutcome::default();
+        let mut suggestions = fuzzy::Suggestions::new();
+        let mut suggest = suggest && !matches!(search_type, SearchType::Semantic);
+        let mut res = Vec::new();
+        for collection in &collections {
+            // Collections can index different fields, so the query is parsed for each
+            let fts_query = match search_type {
+                SearchType::Semantic => None,
+                _ => {
+                    let node = self.parse_query_for(collection, query, options)?;
+                    match &node {
+                        Some(node) if suggest => {
+                            self.collect_suggestions(collection, node, &mut suggestions)?
+                        }
+                        Some(_) => {}
+                        None => suggest = false,
+                    }
+                    let explanation =
+                        self.rewrite_query(collection, node, query_embedding.as_deref(), options)?;
+                    let fts_query = explanation.fts_query.clone();
+                    if options.explain {
+                        outcome.query_explanations.push(explanation);
+                    }
+                    fts_query
+                }
+            };
+            res.extend(match search_type {
+                SearchType::FullText => {
+                    self.search_fulltext_only(collection, fts_query.as_deref(), options)
+                }
+                SearchType::Semantic => self.search_semantic_only(
+                    collection,
+                    query_embedding.as_deref().unwrap_or_default(),
+                    options,
+                ),
+                SearchType::Hybrid => self.search_hybrid(
+                    collection,
+                    fts_query.as_deref(),
+                    query_embedding.as_deref(),
+                    options,
+                ),
+            }?);
+        }
+        if suggest {
+            outcome.suggestion = fuzzy::suggestion(query, &suggestions);
+        }
+        outcome.results = self.finish_results(res, collections.len() > 1, options)?;
+        Ok(outcome)
+    }
+
+    /// Orders results merged from several collections, applies the limit, or MMR if
+    /// requested, and adds the filters to explanations.
+    fn finish_results(
+        &self,
+        mut results: Vec<SearchResult>,
+        merged: bool,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        // Scores are normalized per collection, so merged results are interleaved by score
+        if merged {
+            results.sort_by(|a, b| {
+                b.final_score
+                    .partial_cmp(&a.final_score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        let limit = options.limit.unwrap_or(10);
+        match options.mmr_lambda {
+            Some(lambda) => results = self.diversify(results, lambda, limit)?,
+            None => results.truncate(limit),
+        }
+        if options.explain {
+            let filters = Self::describe_filters(options);
+            for explanation in results.iter_mut().filter_map(|r| r.explanation.as_mut()) {
+                explanation.filters = filters.clone();
+            }
+        }
+        Ok(results)
+    }
 
-        // For normalized embeddings, cosine similarity is just the dot product
-        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-        dot_product as f64
+    /// Describes the path and metadata filters of a search for [`ScoreExplanation`].
+    fn describe_filters(options: &SearchOptions) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(patterns) = options.path_filters.as_ref().filter(|f| !f.is_empty()) {
+            let patterns = patterns
+                .iter()
+                .map(|pattern| format!("{:?}", pattern))
+                .collect::<Vec<_>>();
+            filters.push(format!("path contains {}", patterns.join(" or ")));
+        }
+        if let Some(metadata_filters) = &options.metadata_filters {
+            let mut pairs: Vec<_> = metadata_filters.iter().collect();
+            pairs.sort();
+            filters.extend(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| format!("metadata {} = {:?}", key, value)),
+            );
+        }
+        filters
+    }
+
+    /// Builds the SQL conditions (to be joined with AND) and their parameters for the
+    /// path and metadata filters of a search. Documents are aliased as `d`.
+    fn filter_conditions(options: &SearchOptions) -> (Vec<String>, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        if let Some(filters) = options.path_filters.as_ref().filter(|f| !f.is_empty()) {
+            let like_conditions = filters
+                .iter()
+                .map(|_| "d.path LIKE '%' || ? || '%'")
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            conditions.push(format!("({})", like_conditions));
+            params.extend(filters.iter().cloned());
+        }
+
+        if let Some(metadata_filters) = &options.metadata_filters {
+            Self::push_metadata_conditions(metadata_filters, &mut conditions, &mut params);
+        }
+
+        (conditions, params)
+    }
+
+    /// Adds one `json_extract` equality condition per metadata filter.
+    fn push_metadata_conditions(
+        metadata_filters: &HashMap<String, String>,
+        conditions: &mut Vec<String>,
+        params: &mut Vec<String>,
+    ) {
+        let mut keys: Vec<_> = metadata_filters.keys().collect();
+        keys.sort();
+        for key in keys {
+            conditions.push("json_extract(d.metadata, ?) = ?".to_string());
+            params.push(format!("$.\"{}\"", key));
+            params.push(metadata_filters[key].clone());
+        }
+    }
+
+    fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
+        embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+    }
+
+    fn embedding_from_bytes(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+
+    fn softmax(scores: &[f64]) -> Vec<f64> {
+        let max_score = scores.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let exp_scores: Vec<f64> = scores
+            .iter()
+            .map(|&score| (score - max_score).exp())
+            .collect();
+        let sum_exp: f64 = exp_scores.iter().sum();
+        exp_scores
+            .iter()
+            .map(|&exp_score| {
+                if sum_exp > 0.0 {
+                    exp_score / sum_exp
+                } else {
+                    1.0 / scores.len() as f64
+                }
+            })
+            .collect()
+    }
+
+    fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+
+        // Precomputed embeddings need not be normalized like the embedder's
+        let dot_product: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+        let norm = |v: &[f32]| v.iter().map(|x| *x as f64 * *x as f64).sum::<f64>().sqrt();
+        let norms = norm(a) * norm(b);
+        if norms == 0.0 {
+            return 0.0;
+        }
+        dot_product / norms
     }
 }
 
-impl DocumentIndexer for SqliteLocalSearchEngine {
-    /// Inserts a new document into the database with FTS and embedding support.
-    fn insert_document(&self, request: DocumentRequest) -> anyhow::Result<()> {
+impl SqliteLocalSearchEngine {
+    /// Returns the embedding to store for `content`: the precomputed one if given,
+    /// otherwise one computed by the embedder (if available). Either must have the
+    /// collection's dimension.
+    fn resolve_embedding(
+        &self,
+        content: &str,
+        embedding: Option<Vec<f32>>,
+    ) -> Result<Option<Vec<f32>>> {
+        let embedding = match (embedding, &self.embedder) {
+            (Some(embedding), _) => Some(embedding),
+            (None, Some(embedder)) => Some(embedder.embed_text(content)?),
+            (None, None) => None,
+        };
+        self.check_embedding_dimensions(&embedding)?;
+        Ok(embedding)
+    }
+
+    /// Returns the dimension of a collection's stored embeddings, if it has any.
+    fn stored_dimension(&self, collection: &Collection) -> Result<Option<usize>> {
+        let stored_bytes: Option<i64> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT length(embedding) FROM {} LIMIT 1",
+                    collection.embeddings
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to read embedding dimension", e))?;
+        Ok(stored_bytes.map(|bytes| bytes as usize / 4))
+    }
+
+    /// Checks that embeddings are not empty and have the dimension of those already
+    /// stored, or of the embedder's if none are stored yet.
+    fn check_embedding_dimensions<'a>(
+        &self,
+        embeddings: impl IntoIterator<Item = &'a Vec<f32>>,
+    ) -> Result<()> {
+        let mut expected = match self.stored_dimension(&self.collection)? {
+            Some(dimension) => Some(dimension),
+            None => self.embedder.as_ref().map(|e| e.dimension()).transpose()?,
+        };
+        for embedding in embeddings {
+            if embedding.is_empty() {
+                return Err(LocalSearchError::InvalidArgument(
+                    "Document embeddings must not be empty".to_string(),
+                ));
+            }
+            match expected {
+                Some(expected) if expected != embedding.len() => {
+                    return Err(LocalSearchError::DimensionMismatch {
+                        expected,
+                        actual: embedding.len(),
+                    });
+                }
+                _ => expected = Some(embedding.len()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts a document with an embedding from [`Self::resolve_embedding`].
+    fn insert_with_embedding(
+        &self,
+        request: DocumentRequest,
+        embedding: Option<Vec<f32>>,
+    ) -> Result<()> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs_f64();
-        let metadata_str = serde_json::to_string(&request.metadata)
-            .map_err(|e| anyhow!("Failed to serialize metadata: {}", e))?;
+        let metadata_str = serde_json::to_string(&request.metadata).map_err(|e| {
+            LocalSearchError::InvalidData(format!("Failed to serialize metadata: {}", e))
+        })?;
         let created_at = now;
         let updated_at = now;
 
-        let rows_affected = self.conn.execute("INSERT INTO documents (path, content, metadata, createdAt, updatedAt) values (?1, ?2, ?3, ?4, ?5)", rusqlite::params![request.path, request.content, metadata_str, created_at, updated_at])
-            .map_err(|e| anyhow!("Failed to insert document: {}", e))?;
+        let rows_affected = self.conn.execute(&format!("INSERT INTO {} (path, content, metadata, createdAt, updatedAt) values (?1, ?2, ?3, ?4, ?5)", self.collection.documents), rusqlite::params![request.path, request.content, metadata_str, created_at, updated_at])
+            .map_err(|e| LocalSearchError::sqlite("Failed to insert document", e))?;
         debug!(
             "Inserted document with path: {}. Number of rows affected: {}",
             request.path, rows_affected
         );
 
-        // Generate and store embedding if embedder is available
-        if let Some(ref embedder) = self.embedder {
-            let embedding = embedder.embed_text(&request.content)?;
-            let embedding_bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+        // Store the embedding if one was provided or the embedder is available
+        if let Some(embedding) = embedding {
+            let embedding_bytes = Self::embedding_to_bytes(&embedding);
             self.conn
                 .execute(
-                    "INSERT INTO document_embeddings (path, embedding) VALUES (?1, ?2)",
+                    &format!(
+                        "INSERT INTO {} (path, embedding) VALUES (?1, ?2)",
+                        self.collection.embeddings
+                    ),
                     rusqlite::params![request.path, embedding_bytes],
                 )
-                .map_err(|e| anyhow!("Failed to insert embedding: {}", e))?;
+                .map_err(|e| LocalSearchError::sqlite("Failed to insert embedding", e))?;
             debug!(
                 "Inserted embedding for document with path: {}",
                 request.path
@@ -474,12 +1537,7 @@ impl DocumentIndexer for SqliteLocalSearchEngine {
         }
 
         // Insert into FTS table for search
-        self.conn
-            .execute(
-                "INSERT INTO documents_fts (path, content) VALUES (?1, ?2)",
-                rusqlite::params![request.path, request.content],
-            )
-            .map_err(|e| anyhow!("Failed to insert into FTS: {}", e))?;
+        self.insert_fts_entry(&request.path)?;
         debug!(
             "Inserted document into FTS table with path: {}",
             request.path
@@ -487,23 +1545,31 @@ impl DocumentIndexer for SqliteLocalSearchEngine {
         Ok(())
     }
 
-    /// Updates an existing document or inserts a new one if it doesn't exist.
-    fn upsert_document(&self, request: DocumentRequest) -> anyhow::Result<()> {
+    /// Upserts a document with an embedding from [`Self::resolve_embedding`].
+    fn upsert_with_embedding(
+        &self,
+        request: DocumentRequest,
+        embedding: Option<Vec<f32>>,
+    ) -> Result<()> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs_f64();
-        let metadata_str = serde_json::to_string(&request.metadata)
-            .map_err(|e| anyhow!("Failed to serialize metadata: {}", e))?;
+        let metadata_str = serde_json::to_string(&request.metadata).map_err(|e| {
+            LocalSearchError::InvalidData(format!("Failed to serialize metadata: {}", e))
+        })?;
         let updated_at = now;
 
         let rows_affected = self
             .conn
             .execute(
-                "UPDATE documents SET content = ?1, metadata = ?2, updatedAt = ?3 WHERE path = ?4",
+                &format!(
+                    "UPDATE {} SET content = ?1, metadata = ?2, updatedAt = ?3 WHERE path = ?4",
+                    self.collection.documents
+                ),
                 rusqlite::params![request.content, metadata_str, updated_at, request.path],
             )
-            .map_err(|e| anyhow!("Failed to update document: {}", e))?;
+            .map_err(|e| LocalSearchError::sqlite("Failed to update document", e))?;
 
         if rows_affected == 0 {
             // Document does not exist, insert new
@@ -511,67 +1577,271 @@ impl DocumentIndexer for SqliteLocalSearchEngine {
                 "Document with path: {} did not exist. Inserting new document.",
                 request.path
             );
-            self.insert_document(request)?;
+            self.insert_with_embedding(request, embedding)?;
         } else {
             debug!(
                 "Updated document with path: {}. Number of rows affected: {}",
                 request.path, rows_affected
             );
 
-            // Update embedding if embedder is available
-            if let Some(ref embedder) = self.embedder {
-                let embedding = embedder.embed_text(&request.content)?;
-                let embedding_bytes: Vec<u8> =
-                    embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+            // Update the embedding if one was provided or the embedder is available
+            if let Some(embedding) = embedding {
+                let embedding_bytes = Self::embedding_to_bytes(&embedding);
                 self.conn
                     .execute(
-                        "UPDATE document_embeddings SET embedding = ?1 WHERE path = ?2",
-                        rusqlite::params![embedding_bytes, request.path],
+                        &format!(
+                            "INSERT OR REPLACE INTO {} (path, embedding) VALUES (?1, ?2)",
+                            self.collection.embeddings
+                        ),
+                        rusqlite::params![request.path, embedding_bytes],
                     )
-                    .map_err(|e| anyhow!("Failed to update embedding: {}", e))?;
+                    .map_err(|e| LocalSearchError::sqlite("Failed to update embedding", e))?;
                 debug!("Updated embedding for document with path: {}", request.path);
             }
 
-            // Update FTS table
+            // Update FTS table; metadata fields may have changed too
             self.conn
                 .execute(
-                    "UPDATE documents_fts SET content = ?1 WHERE path = ?2",
-                    rusqlite::params![request.content, request.path],
+                    &format!("DELETE FROM {} WHERE path = ?1", self.collection.fts),
+                    rusqlite::params![request.path],
                 )
-                .map_err(|e| anyhow!("Failed to update FTS: {}", e))?;
+                .map_err(|e| LocalSearchError::sqlite("Failed to update FTS", e))?;
+            self.insert_fts_entry(&request.path)?;
             debug!("Updated FTS entry for document with path: {}", request.path);
         }
         Ok(())
     }
+}
 
-    /// Removes a document and its associated embeddings and FTS entries by path.
-    fn delete_document(&self, path: &str) -> anyhow::Result<()> {
-        // Delete from child tables first to avoid foreign key constraint violations
-        if self.embedder.is_some() {
-            self.conn
-                .execute(
-                    "DELETE FROM document_embeddings WHERE path = ?1",
-                    rusqlite::params![path],
-                )
-                .map_err(|e| anyhow!("Failed to delete embedding: {}", e))?;
-            debug!("Deleted embedding for document with path: {}", path);
+impl SqliteLocalSearchEngine {
+    /// Writes every document, and optionally its embedding, to `writer` in a portable
+    /// format. Returns the number of documents written.
+    pub fn export_to<W: Write>(&self, writer: W, options: &ExportOptions) -> Result<usize> {
+        let (embedding_model, embedding_dimension) = if options.include_embeddings {
+            let model = match self.get_meta(&self.collection.model_key)? {
+                Some(model) => Some(model),
+                None => self.embedder.as_ref().map(|e| e.model_id().to_string()),
+            };
+            (model, self.stored_dimension(&self.collection)?)
+        } else {
+            (None, None)
+        };
+        let header = ExportHeader {
+            format_version: FORMAT_VERSION,
+            embedding_model,
+            embedding_dimension,
+            includes_embeddings: options.include_embeddings,
+        };
+        let mut export = ExportWriter::new(writer, options.format, &header)?;
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT d.path, d.content, d.metadata, d.createdAt, d.updatedAt, e.embedding
+                 FROM {} d
+                 LEFT JOIN {} e ON d.path = e.path
+                 ORDER BY d.path",
+                self.collection.documents, self.collection.embeddings
+            ))
+            .map_err(|e| LocalSearchError::sqlite("Failed to prepare export query", e))?;
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| LocalSearchError::sqlite("Failed to query documents", e))?;
+        let mut count = 0;
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| LocalSearchError::sqlite("Failed to read document row", e))?
+        {
+            let embedding_bytes: Option<Vec<u8>> = if options.include_embeddings {
+                row.get(5)?
+            } else {
+                None
+            };
+            export.write(&ExportedDocument {
+                document: StoredDocument {
+                    path: row.get(0)?,
+                    content: row.get(1)?,
+                    metadata: serde_json::from_str(&row.get::<_, String>(2)?)
+                        .ok()
+                        .flatten(),
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                },
+                embedding: embedding_bytes.map(|bytes| Self::embedding_from_bytes(&bytes)),
+            })?;
+            count += 1;
+        }
+        export.finish()?;
+        info!("Exported {} documents", count);
+        Ok(count)
+    }
+
+    /// Imports an export created by [`export_to`](Self::export_to), keeping timestamps
+    /// and replacing documents with the same path.
+    ///
+    /// Exported embeddings are stored as is when they come from the model this index
+    /// uses (the embedder's model, or the recorded one if there is no embedder);
+    /// otherwise documents are embedded again if an embedder is available.
+    pub fn import_from<R: BufRead>(&self, reader: R) -> Result<ImportSummary> {
+        let export = ExportReader::new(reader)?;
+        let header = export.header().clone();
+        let index_model = match &self.embedder {
+            Some(embedder) => Some(embedder.model_id().to_string()),
+            None => self.get_meta(&self.collection.model_key)?,
+        };
+        let reuse_embeddings = header.includes_embeddings
+            && match (&index_model, &header.embedding_model) {
+                (None, _) => true,
+                (Some(index_model), Some(export_model)) => index_model == export_model,
+                (Some(_), None) => false,
+            };
+        if header.includes_embeddings && !reuse_embeddings {
+            info!(
+                "Export embeddings from model {:?} do not match index model {:?}; re-embedding",
+                header.embedding_model, index_model
+            );
+        }
+        if reuse_embeddings
+            && let (Some(expected), Some(actual)) = (
+                self.stored_dimension(&self.collection)?,
+                header.embedding_dimension,
+            )
+            && expected != actual
+        {
+            return Err(LocalSearchError::DimensionMismatch { expected, actual });
         }
 
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        let mut summary = ImportSummary::default();
+        for exported in export {
+            let exported = exported?;
+            let embedding = match exported.embedding.filter(|_| reuse_embeddings) {
+                Some(embedding) => {
+                    summary.reused_embeddings += 1;
+                    Some(embedding)
+                }
+                None => {
+                    let embedding = self.resolve_embedding(&exported.document.content, None)?;
+                    if embedding.is_some() {
+                        summary.computed_embeddings += 1;
+                    }
+                    embedding
+                }
+            };
+            self.restore_document(&exported.document, embedding)?;
+            summary.document_count += 1;
+        }
+        if self.embedder.is_none()
+            && summary.reused_embeddings > 0
+            && let Some(model) = &header.embedding_model
+        {
+            self.set_meta(&self.collection.model_key, model)?;
+        }
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit import", e))?;
+
+        info!(
+            "Imported {} documents ({} embeddings reused, {} computed)",
+            summary.document_count, summary.reused_embeddings, summary.computed_embeddings
+        );
+        Ok(summary)
+    }
+
+    /// Writes a document with its original timestamps, replacing any existing one.
+    fn restore_document(
+        &self,
+        document: &StoredDocument,
+        embedding: Option<Vec<f32>>,
+    ) -> Result<()> {
+        let metadata_str = serde_json::to_string(&document.metadata).map_err(|e| {
+            LocalSearchError::InvalidData(format!("Failed to serialize metadata: {}", e))
+        })?;
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {} (path, content, metadata, createdAt, updatedAt) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    self.collection.documents
+                ),
+                rusqlite::params![
+                    document.path,
+                    document.content,
+                    metadata_str,
+                    document.created_at,
+                    document.updated_at
+                ],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to import document", e))?;
+
         self.conn
             .execute(
-                "DELETE FROM documents_fts WHERE path = ?1",
+                &format!("DELETE FROM {} WHERE path = ?1", self.collection.fts),
+                rusqlite::params![document.path],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to update FTS", e))?;
+        self.insert_fts_entry(&document.path)?;
+
+        match embedding {
+            Some(embedding) => self.conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {} (path, embedding) VALUES (?1, ?2)",
+                    self.collection.embeddings
+                ),
+                rusqlite::params![document.path, Self::embedding_to_bytes(&embedding)],
+            ),
+            None => self.conn.execute(
+                &format!("DELETE FROM {} WHERE path = ?1", self.collection.embeddings),
+                rusqlite::params![document.path],
+            ),
+        }
+        .map_err(|e| LocalSearchError::sqlite("Failed to import embedding", e))?;
+        debug!("Imported document with path: {}", document.path);
+        Ok(())
+    }
+}
+
+impl DocumentIndexer for SqliteLocalSearchEngine {
+    /// Inserts a new document into the database with FTS and embedding support.
+    fn insert_document(&self, mut request: DocumentRequest) -> Result<()> {
+        let embedding = self.resolve_embedding(&request.content, request.embedding.take())?;
+        self.insert_with_embedding(request, embedding)
+    }
+
+    /// Updates an existing document or inserts a new one if it doesn't exist.
+    fn upsert_document(&self, mut request: DocumentRequest) -> Result<()> {
+        let embedding = self.resolve_embedding(&request.content, request.embedding.take())?;
+        self.upsert_with_embedding(request, embedding)
+    }
+
+    /// Removes a document and its associated embeddings and FTS entries by path.
+    fn delete_document(&self, path: &str) -> Result<()> {
+        // Delete from child tables first to avoid foreign key constraint violations.
+        // Embeddings may have been precomputed, so remove them even without an embedder.
+        self.conn
+            .execute(
+                &format!("DELETE FROM {} WHERE path = ?1", self.collection.embeddings),
                 rusqlite::params![path],
             )
-            .map_err(|e| anyhow!("Failed to delete from FTS: {}", e))?;
+            .map_err(|e| LocalSearchError::sqlite("Failed to delete embedding", e))?;
+        debug!("Deleted embedding for document with path: {}", path);
+
+        self.conn
+            .execute(
+                &format!("DELETE FROM {} WHERE path = ?1", self.collection.fts),
+                rusqlite::params![path],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to delete from FTS", e))?;
         debug!("Deleted FTS entry for document with path: {}", path);
 
         let rows_affected = self
             .conn
             .execute(
-                "DELETE FROM documents WHERE path = ?1",
+                &format!("DELETE FROM {} WHERE path = ?1", self.collection.documents),
                 rusqlite::params![path],
             )
-            .map_err(|e| anyhow!("Failed to delete document: {}", e))?;
+            .map_err(|e| LocalSearchError::sqlite("Failed to delete document", e))?;
         debug!(
             "Deleted document with path: {}. Number of rows affected: {}",
             path, rows_affected
@@ -580,27 +1850,237 @@ impl DocumentIndexer for SqliteLocalSearchEngine {
         Ok(())
     }
 
+    /// Returns the stored document for a path, or `None` if it is not indexed.
+    fn get_document(&self, path: &str) -> Result<Option<StoredDocument>> {
+        let document = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT path, content, metadata, createdAt, updatedAt FROM {} WHERE path = ?1",
+                    self.collection.documents
+                ),
+                rusqlite::params![path],
+                |row| {
+                    Ok(StoredDocument {
+                        path: row.get(0)?,
+                        content: row.get(1)?,
+                        metadata: serde_json::from_str(&row.get::<_, String>(2)?)
+                            .ok()
+                            .flatten(),
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to get document", e))?;
+        debug!(
+            "Get document with path: {} found: {}",
+            path,
+            document.is_some()
+        );
+        Ok(document)
+    }
+
+    /// Lists documents ordered by path, filtered by path prefix and metadata.
+    fn list_documents(&self, options: &ListOptions) -> Result<Vec<DocumentInfo>> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(prefix) = &options.path_prefix {
+            conditions.push("substr(d.path, 1, length(?)) = ?".to_string());
+            params.push(prefix.clone());
+            params.push(prefix.clone());
+        }
+        if let Some(metadata_filters) = &options.metadata_filters {
+            Self::push_metadata_conditions(metadata_filters, &mut conditions, &mut params);
+        }
+
+        let sql = format!(
+            "SELECT d.path, d.metadata, d.createdAt, d.updatedAt FROM {} d{} ORDER BY d.path LIMIT {} OFFSET {}",
+            self.collection.documents,
+            if conditions.is_empty() {
+                String::new()
+            } else {
+                format!(" WHERE {}", conditions.join(" AND "))
+            },
+            options.limit.map_or(-1, |limit| limit as i64),
+            options.offset
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| LocalSearchError::sqlite("Failed to prepare list query", e))?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        let documents = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                Ok(DocumentInfo {
+                    path: row.get(0)?,
+                    metadata: serde_json::from_str(&row.get::<_, String>(1)?)
+                        .ok()
+                        .flatten(),
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| LocalSearchError::sqlite("Failed to list documents", e))?;
+        debug!("Listed {} documents", documents.len());
+        Ok(documents)
+    }
+
+    /// Deletes all documents under a path prefix in a single transaction.
+    fn delete_prefix(&self, prefix: &str) -> Result<usize> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        for table in [&self.collection.embeddings, &self.collection.fts] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE substr(path, 1, length(?1)) = ?1",
+                    table
+                ),
+                rusqlite::params![prefix],
+            )
+            .map_err(|e| LocalSearchError::sqlite(format!("Failed to delete from {}", table), e))?;
+        }
+        let rows_affected = tx
+            .execute(
+                &format!(
+                    "DELETE FROM {} WHERE substr(path, 1, length(?1)) = ?1",
+                    self.collection.documents
+                ),
+                rusqlite::params![prefix],
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to delete documents", e))?;
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit delete", e))?;
+        debug!(
+            "Deleted {} documents with path prefix: {}",
+            rows_affected, prefix
+        );
+        Ok(rows_affected)
+    }
+
     /// Refreshes the database connection to pick up external changes.
-    fn refresh(&mut self) -> anyhow::Result<()> {
+    fn refresh(&mut self) -> Result<()> {
         // Close and reopen the connection to refresh from underlying database changes
         let db_path = self.db_path.clone();
-        let new_conn =
-            Connection::open(&db_path).map_err(|e| anyhow!("Failed to reopen database: {}", e))?;
+        let new_conn = if self.snapshot {
+            Self::open_snapshot_connection(&db_path)?
+        } else {
+            Connection::open(&db_path)
+                .map_err(|e| LocalSearchError::sqlite("Failed to reopen database", e))?
+        };
         let old_conn = std::mem::replace(&mut self.conn, new_conn);
         old_conn
             .close()
-            .map_err(|e| anyhow!("Failed to close database connection: {}", e.1))?;
+            .map_err(|e| LocalSearchError::sqlite("Failed to close database connection", e.1))?;
         info!("Database connection refreshed for path: {:?}", self.db_path);
         Ok(())
     }
 
-    /// Returns the total number of documents currently indexed in the database.
-    fn stats(&self) -> anyhow::Result<i64> {
-        let count: i64 = self
+    /// Returns the embedder used to compute document embeddings, if any.
+    fn embedder(&self) -> Option<&LocalEmbedder> {
+        self.embedder.as_deref()
+    }
+
+    /// Upserts a batch of documents inside a single transaction.
+    fn upsert_batch(
+        &self,
+        requests: Vec<DocumentRequest>,
+        embeddings: Option<Vec<Vec<f32>>>,
+    ) -> Result<()> {
+        if let Some(ref embeddings) = embeddings
+            && embeddings.len() != requests.len()
+        {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "Expected {} embeddings for batch, got {}",
+                requests.len(),
+                embeddings.len()
+            )));
+        }
+        let tx = self
             .conn
-            .query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0))?;
-        info!("Total documents indexed: {}", count);
-        Ok(count)
+            .unchecked_transaction()
+            .map_err(|e| LocalSearchError::sqlite("Failed to start transaction", e))?;
+        let mut embeddings = embeddings.map(|e| e.into_iter());
+        for mut request in requests {
+            // Always advance, so the embeddings stay aligned with the requests
+            let batch_embedding = embeddings.as_mut().and_then(|e| e.next());
+            let embedding = self.resolve_embedding(
+                &request.content,
+                request.embedding.take().or(batch_embedding),
+            )?;
+            self.upsert_with_embedding(request, embedding)?;
+        }
+        tx.commit()
+            .map_err(|e| LocalSearchError::sqlite("Failed to commit batch", e))?;
+        Ok(())
+    }
+
+    /// Returns counts, full-text index health, embedding model and database size.
+    fn stats(&self) -> Result<IndexStats> {
+        let Collection {
+            documents,
+            fts,
+            embeddings,
+            ..
+        } = &self.collection;
+        let count = |sql: &str| -> Result<i64> {
+            self.conn
+                .query_row(sql, [], |row| row.get(0))
+                .map_err(|e| LocalSearchError::sqlite("Failed to read index stats", e))
+        };
+        let embedding_bytes: Option<i64> = self
+            .conn
+            .query_row(
+                &format!("SELECT length(embedding) FROM {} LIMIT 1", embeddings),
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to read index stats", e))?;
+        let last_updated_at: Option<f64> = self
+            .conn
+            .query_row(
+                &format!("SELECT MAX(updatedAt) FROM {}", documents),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to read index stats", e))?;
+        // The pragma form of the FTS5 integrity check also works on read-only snapshots
+        let fts_integrity_ok = self
+            .conn
+            .query_row(&format!("PRAGMA integrity_check({})", fts), [], |row| {
+                row.get::<_, String>(0)
+            })
+            .is_ok_and(|result| result == "ok");
+
+        let embedding_count = count(&format!("SELECT COUNT(*) FROM {}", embeddings))?;
+        let stats = IndexStats {
+            document_count: count(&format!("SELECT COUNT(*) FROM {}", documents))?,
+            embedding_count,
+            chunk_count: embedding_count,
+            documents_missing_embeddings: count(&format!(
+                "SELECT COUNT(*) FROM {} d
+                 WHERE NOT EXISTS (SELECT 1 FROM {} e WHERE e.path = d.path)",
+                documents, embeddings
+            ))?,
+            fts_row_count: count(&format!("SELECT COUNT(*) FROM {}", fts))?,
+            fts_integrity_ok,
+            total_content_bytes: count(&format!(
+                "SELECT COALESCE(SUM(length(CAST(content AS BLOB))), 0) FROM {}",
+                documents
+            ))?,
+            embedding_dimension: embedding_bytes.map(|bytes| bytes as usize / 4),
+            embedding_model: self.get_meta(&self.collection.model_key)?,
+            db_size_bytes: (count("PRAGMA page_count")? * count("PRAGMA page_size")?) as u64,
+            last_updated_at,
+        };
+        info!("Total documents indexed: {}", stats.document_count);
+        Ok(stats)
     }
 }
 
@@ -612,25 +2092,105 @@ impl LocalSearch for SqliteLocalSearchEngine {
         search_type: SearchType,
         top: Option<i8>,
         path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>> {
-        let res = match search_type {
-            SearchType::FullText => self.search_fulltext_only(query, path_filters),
-            SearchType::Semantic => {
-                if self.embedder.is_none() {
-                    return Err(anyhow!("Semantic search requires an embedder"));
+    ) -> Result<Vec<SearchResult>> {
+        let options = SearchOptions {
+            limit: top.map(|top| top as usize),
+            path_filters: path_filters.map(|filters| filters.to_vec()),
+            ..SearchOptions::default()
+        };
+        self.search_with_options(query, search_type, &options)
+    }
+
+    /// Performs a search with path and metadata filters applied before ranking.
+    fn search_with_options(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self
+            .search_outcome(query, search_type, options, false)?
+            .results)
+    }
+
+    /// Searches once, collecting the suggestion and query explanations while the
+    /// query is rewritten.
+    fn search_with_details(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        self.search_outcome(query, search_type, options, true)
+    }
+
+    /// Ranks documents by cosine similarity to a precomputed embedding.
+    fn search_by_vector(
+        &self,
+        embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if embedding.is_empty() {
+            return Err(LocalSearchError::InvalidArgument(
+                "The search embedding is empty".to_string(),
+            ));
+        }
+        let collections = self.search_collections(options)?;
+        let mut results = Vec::new();
+        for collection in &collections {
+            match self.stored_dimension(collection)? {
+                Some(expected) if expected != embedding.len() => {
+                    return Err(LocalSearchError::DimensionMismatch {
+                        expected,
+                        actual: embedding.len(),
+                    });
                 }
-                self.search_semantic_only(query, path_filters)
+                _ => results.extend(self.semantic_ranking(collection, embedding, options)?),
             }
-            SearchType::Hybrid => self.search_hybrid(query, path_filters),
-        }?;
-        let limit = std::cmp::min(top.unwrap_or(10) as usize, res.len());
-        Ok(res.into_iter().take(limit).collect::<Vec<_>>())
+        }
+        self.finish_results(results, collections.len() > 1, options)
+    }
+
+    /// Finds documents similar to one in the engine's collection, without
+    /// re-embedding it.
+    fn find_similar(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.similar_documents(path, terms, options)
+    }
+
+    /// Replaces words found in none of the searched collections with the closest
+    /// index term found in any of them.
+    fn suggest(&self, query: &str, options: &SearchOptions) -> Result<Option<String>> {
+        let mut suggestions = fuzzy::Suggestions::new();
+        for collection in &self.search_collections(options)? {
+            let Some(node) = self.parse_query_for(collection, query, options)? else {
+                return Ok(None);
+            };
+            self.collect_suggestions(collection, &node, &mut suggestions)?;
+        }
+        Ok(fuzzy::suggestion(query, &suggestions))
+    }
+
+    fn explain_query(&self, query: &str, options: &SearchOptions) -> Result<Vec<QueryExplanation>> {
+        let query_embedding = self.query_embedding(query, &SearchType::FullText, options)?;
+        self.search_collections(options)?
+            .iter()
+            .map(|collection| {
+                let node = self.parse_query_for(collection, query, options)?;
+                self.rewrite_query(collection, node, query_embedding.as_deref(), options)
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{QueryExpansion, QuerySyntax};
     use std::collections::HashMap;
     use tempfile::TempDir;
 
@@ -662,6 +2222,7 @@ mod tests {
             path: path.to_string(),
             content: content.to_string(),
             metadata: Some(metadata),
+            embedding: None,
         }
     }
 
@@ -679,86 +2240,609 @@ mod tests {
     }
 
     #[test]
-    fn test_document_insertion() {
+    fn test_document_insertion() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        let doc = create_test_document(
+            "test1.txt",
+            "This is a test document about Rust programming.",
+        );
+        let result = engine.insert_document(doc);
+        assert!(result.is_ok());
+
+        let count = engine.stats().unwrap().document_count;
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_document_upsert() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        // Insert initial document
+        let doc1 = create_test_document("test1.txt", "Original content");
+        engine.insert_document(doc1).unwrap();
+
+        // Upsert with new content
+        let doc2 = create_test_document("test1.txt", "Updated content about machine learning");
+        let result = engine.upsert_document(doc2);
+        assert!(result.is_ok());
+
+        // Should still have only 1 document
+        let count = engine.stats().unwrap().document_count;
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_document_deletion() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        // Insert a document
+        let doc = create_test_document("test1.txt", "This document will be deleted");
+        engine.insert_document(doc).unwrap();
+        assert_eq!(engine.stats().unwrap().document_count, 1);
+
+        // Delete the document
+        let result = engine.delete_document("test1.txt");
+        assert!(result.is_ok());
+
+        // Should have 0 documents now
+        let count = engine.stats().unwrap().document_count;
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_fulltext_search() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        // Insert test documents
+        let docs = vec![
+            create_test_document("rust1.txt", "Rust programming language is memory safe"),
+            create_test_document("python1.txt", "Python is a high-level programming language"),
+            create_test_document("js1.txt", "JavaScript is used for web development"),
+        ];
+
+        for doc in docs {
+            engine.insert_document(doc).unwrap();
+        }
+
+        // Search for "rust"
+        let results = engine
+            .search("programming", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results.len(), 2); // Should match rust1.txt
+
+        // All results should have FTS scores but no semantic scores
+        for result in &results {
+            println!(
+                "FTS Score: {:?}, Semantic Score: {:?}",
+                result.fts_score, result.semantic_score
+            );
+            assert!(result.fts_score.is_some() && result.fts_score.unwrap() > 0.1);
+            assert!(result.semantic_score.is_none());
+        }
+    }
+
+    #[test]
+    fn test_query_syntax_modes() {
+        let (engine, _temp_dir) = create_test_engine();
+        engine
+            .insert_document(create_test_document(
+                "cpp.txt",
+                "Modern C++ and foo-bar templates AND more",
+            ))
+            .unwrap();
+        engine
+            .insert_document(create_test_document(
+                "rust.txt",
+                "Rust templates are generics",
+            ))
+            .unwrap();
+
+        // Plain queries never reach SQLite as syntax, in full-text or hybrid mode
+        for query in ["C++", "foo-bar", "\"unbalanced", "AND", "++"] {
+            for search_type in [SearchType::FullText, SearchType::Hybrid] {
+                assert!(engine.search(query, search_type, Some(10), None).is_ok());
+            }
+        }
+        let results = engine
+            .search("foo-bar", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "cpp.txt");
+
+        let advanced = |query: &str| {
+            let options = SearchOptions {
+                query_syntax: QuerySyntax::Advanced,
+                ..SearchOptions::default()
+            };
+            engine.search_with_options(query, SearchType::FullText, &options)
+        };
+        let paths = |results: Vec<SearchResult>| {
+            let mut paths: Vec<_> = results.into_iter().map(|r| r.path).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            paths(advanced("templates NOT rust").unwrap()),
+            vec!["cpp.txt"]
+        );
+        assert_eq!(
+            paths(advanced("temp* OR \"are generics\"").unwrap()),
+            vec!["cpp.txt", "rust.txt"]
+        );
+        assert_eq!(
+            paths(advanced("NEAR(modern templates, 5)").unwrap()),
+            vec!["cpp.txt"]
+        );
+        assert!(advanced("NEAR(modern templates, 2)").unwrap().is_empty());
+        assert!(matches!(
+            advanced("(templates OR"),
+            Err(LocalSearchError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_field_scoped_search() {
+        let (engine, _temp_dir) = create_test_engine();
+        let with_title = |path: &str, content: &str, title: &str| DocumentRequest {
+            path: path.to_string(),
+            content: content.to_string(),
+            metadata: Some(HashMap::from([("title".to_string(), title.to_string())])),
+            embedding: None,
+        };
+        engine
+            .insert_document(with_title(
+                "notes/rust_guide.md",
+                "Ownership basics",
+                "Rust",
+            ))
+            .unwrap();
+        engine
+            .insert_document(with_title("notes/misc.md", "Rust in passing", "Misc"))
+            .unwrap();
+
+        // Only content is indexed by default
+        assert!(
+            engine
+                .search("guide", SearchType::FullText, Some(10), None)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Existing documents are reindexed when the fields change
+        engine
+            .set_search_fields(
+                &SearchFields::new()
+                    .with_path(1.0)
+                    .with_metadata("title", 10.0),
+            )
+            .unwrap();
+        assert_eq!(engine.search_fields().unwrap().names().len(), 3);
+        engine
+            .upsert_document(with_title("notes/other.md", "Unrelated", "Rust tips"))
+            .unwrap();
+        let search = |query: &str| {
+            engine
+                .search(query, SearchType::FullText, Some(10), None)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("guide"), vec!["notes/rust_guide.md"]);
+        assert_eq!(search("path:guide"), vec!["notes/rust_guide.md"]);
+        assert_eq!(search("content:rust"), vec!["notes/misc.md"]);
+        let mut titled = search("title:rust");
+        titled.sort();
+        assert_eq!(titled, vec!["notes/other.md", "notes/rust_guide.md"]);
+        // The title weight ranks title matches above content matches
+        assert_ne!(search("rust").last().unwrap(), "notes/rust_guide.md");
+        assert_eq!(search("rust").last().unwrap(), "notes/misc.md");
+        assert_eq!(engine.stats().unwrap().fts_row_count, 3);
+
+        let options = SearchOptions {
+            query_syntax: QuerySyntax::Advanced,
+            ..SearchOptions::default()
+        };
+        let advanced = engine
+            .search_with_options("title:(rust NOT tips)", SearchType::FullText, &options)
+            .unwrap();
+        assert_eq!(advanced.len(), 1);
+        assert_eq!(advanced[0].path, "notes/rust_guide.md");
+        assert!(matches!(
+            engine.search_with_options("author:smith", SearchType::FullText, &options),
+            Err(LocalSearchError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_tokenizers_for_non_english_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let open = |name: &str, tokenizer: Option<Tokenizer>| {
+            let db_path = temp_dir.path().join(name);
+            let mut engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+            if let Some(tokenizer) = tokenizer {
+                engine = engine.with_tokenizer(tokenizer).unwrap();
+            }
+            engine.create_table().unwrap();
+            engine
+        };
+        let count = |engine: &SqliteLocalSearchEngine, query: &str| {
+            engine
+                .search(query, SearchType::FullText, Some(10), None)
+                .unwrap()
+                .len()
+        };
+        let corpus = [
+            ("de.txt", "Die Straße in MÜNCHEN ist schön"),
+            ("fr.txt", "Le café est très élégant"),
+            ("ja.txt", "東京都の天気予報は晴れです"),
+        ];
+
+        let default = open("default.db", None);
+        let unicode = open(
+            "unicode.db",
+            Some(Tokenizer::new(TokenizerKind::Unicode61).with_remove_diacritics(true)),
+        );
+        let trigram = open("trigram.db", Some(Tokenizer::new(TokenizerKind::Trigram)));
+        for engine in [&default, &unicode, &trigram] {
+            for (path, content) in corpus {
+                engine
+                    .insert_document(create_test_document(path, content))
+                    .unwrap();
+            }
+        }
+
+        // ascii folds ASCII case only and keeps accents
+        assert_eq!(count(&default, "münchen"), 0);
+        assert_eq!(count(&default, "cafe"), 0);
+        assert_eq!(count(&unicode, "münchen"), 1);
+        assert_eq!(count(&unicode, "munchen schon"), 1);
+        assert_eq!(count(&unicode, "CAFÉ"), 1);
+
+        // Text without spaces is one unicode61 token, but trigrams match substrings
+        assert_eq!(count(&unicode, "天気予報"), 0);
+        assert_eq!(count(&trigram, "天気予報"), 1);
+        assert_eq!(count(&trigram, "straß"), 1);
+
+        // The tokenizer is persisted and the index survives create_table
+        drop(unicode);
+        let reopened = open("unicode.db", None);
+        assert_eq!(reopened.tokenizer().unwrap().kind, TokenizerKind::Unicode61);
+        assert_eq!(count(&reopened, "munchen"), 1);
+        let db_path = temp_dir.path().join("unicode.db");
+        let mismatched = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None)
+            .unwrap()
+            .with_tokenizer(Tokenizer::default())
+            .unwrap();
+        assert!(matches!(
+            mismatched.create_table(),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+
+        // Changing the tokenizer reindexes the stored documents
+        default
+            .set_tokenizer(Tokenizer::new(TokenizerKind::Unicode61).with_remove_diacritics(true))
+            .unwrap();
+        assert_eq!(count(&default, "munchen"), 1);
+        assert_eq!(default.stats().unwrap().fts_row_count, 3);
+    }
+
+    #[test]
+    fn test_fuzzy_search_and_suggestions() {
         let (engine, _temp_dir) = create_test_engine();
+        engine
+            .insert_document(create_test_document(
+                "embed.md",
+                "Computing embeddings for semantic search",
+            ))
+            .unwrap();
+        engine
+            .insert_document(create_test_document(
+                "lang.md",
+                "Rust is a systems programming language",
+            ))
+            .unwrap();
 
-        let doc = create_test_document(
-            "test1.txt",
-            "This is a test document about Rust programming.",
+        let search = |query: &str, fuzzy: bool| {
+            let options = SearchOptions {
+                fuzzy,
+                ..SearchOptions::default()
+            };
+            engine
+                .search_with_options(query, SearchType::FullText, &options)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect::<Vec<_>>()
+        };
+        assert!(search("emebddings", false).is_empty());
+        assert_eq!(search("emebddings", true), vec!["embed.md"]);
+        assert_eq!(search("rust langauge", true), vec!["lang.md"]);
+        // Short words are not corrected
+        assert!(search("rst", true).is_empty());
+
+        let suggest = |query: &str| engine.suggest(query, &SearchOptions::default()).unwrap();
+        assert_eq!(suggest("rust langauge").as_deref(), Some("rust language"));
+        assert_eq!(
+            suggest("semantc emebddings").as_deref(),
+            Some("semantic embeddings")
         );
-        let result = engine.insert_document(doc);
-        assert!(result.is_ok());
-
-        let count = engine.stats().unwrap();
-        assert_eq!(count, 1);
+        assert_eq!(suggest("rust language"), None);
+        assert_eq!(suggest("zzzzzzzz"), None);
+
+        // One search returns the results, suggestion and rewrite together
+        let options = SearchOptions {
+            fuzzy: true,
+            explain: true,
+            ..SearchOptions::default()
+        };
+        let outcome = engine
+            .search_with_details("rust langauge", SearchType::FullText, &options)
+            .unwrap();
+        assert_eq!(outcome.results[0].path, "lang.md");
+        assert_eq!(outcome.suggestion.as_deref(), Some("rust language"));
+        assert_eq!(
+            outcome.query_explanations[0].corrections["langauge"],
+            vec!["language"]
+        );
+        let outcome = engine
+            .search_with_details("rust langauge", SearchType::Semantic, &options)
+            .map(|outcome| outcome.suggestion);
+        assert!(matches!(outcome, Err(LocalSearchError::MissingEmbedder(_))));
     }
 
     #[test]
-    fn test_document_upsert() {
+    fn test_synonyms_and_query_explanation() {
         let (engine, _temp_dir) = create_test_engine();
-
-        // Insert initial document
-        let doc1 = create_test_document("test1.txt", "Original content");
-        engine.insert_document(doc1).unwrap();
-
-        // Upsert with new content
-        let doc2 = create_test_document("test1.txt", "Updated content about machine learning");
-        let result = engine.upsert_document(doc2);
-        assert!(result.is_ok());
-
-        // Should still have only 1 document
-        let count = engine.stats().unwrap();
-        assert_eq!(count, 1);
+        engine
+            .insert_document(create_test_document(
+                "cluster.md",
+                "Deploying services on Kubernetes",
+            ))
+            .unwrap();
+        engine
+            .insert_document(create_test_document(
+                "ml.md",
+                "An introduction to machine learning",
+            ))
+            .unwrap();
+        let search = |query: &str| {
+            engine
+                .search(query, SearchType::FullText, None, None)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect::<Vec<_>>()
+        };
+        assert!(search("k8s").is_empty());
+
+        let synonyms = Synonyms::new()
+            .with_group(&["k8s", "kubernetes"])
+            .with_group(&["ml", "machine learning"]);
+        engine.set_synonyms(&synonyms).unwrap();
+        assert_eq!(engine.synonyms().unwrap(), synonyms);
+        assert_eq!(search("K8s deploying"), vec!["cluster.md"]);
+        assert_eq!(search("ml"), vec!["ml.md"]);
+
+        let explanation = engine
+            .explain_query("k8s", &SearchOptions::default())
+            .unwrap();
+        assert_eq!(explanation.len(), 1);
+        assert_eq!(
+            explanation[0].fts_query.as_deref(),
+            Some("\"k8s\" OR \"kubernetes\"")
+        );
+        assert_eq!(explanation[0].synonyms["k8s"], vec!["kubernetes"]);
+
+        assert!(matches!(
+            engine.set_synonyms(&Synonyms::new().with_group(&["alone"])),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        // Expansion takes terms from semantic matches, which need an embedder
+        let options = SearchOptions {
+            query_expansion: Some(QueryExpansion::default()),
+            ..SearchOptions::default()
+        };
+        assert!(matches!(
+            engine.search_with_options("k8s", SearchType::FullText, &options),
+            Err(LocalSearchError::MissingEmbedder(_))
+        ));
     }
 
     #[test]
-    fn test_document_deletion() {
+    fn test_search_explanation() {
         let (engine, _temp_dir) = create_test_engine();
+        for (path, content) in [
+            ("src/a.rs", "rust rust rust"),
+            ("src/b.rs", "rust and more words"),
+            ("docs/c.md", "rust"),
+        ] {
+            engine
+                .insert_document(create_test_document(path, content))
+                .unwrap();
+        }
+        let options = SearchOptions {
+            path_filters: Some(vec!["src".to_string()]),
+            explain: true,
+            ..SearchOptions::default()
+        };
+        let results = engine
+            .search_with_options("rust", SearchType::FullText, &options)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        for (i, result) in results.iter().enumerate() {
+            let explanation = result.explanation.as_ref().unwrap();
+            assert_eq!(explanation.fts_rank, Some(i + 1));
+            assert!(explanation.bm25.unwrap() < 0.0);
+            assert_eq!(explanation.fts_softmax, result.fts_score);
+            assert_eq!(explanation.cosine_similarity, None);
+            assert_eq!(
+                (explanation.fts_weight, explanation.semantic_weight),
+                (1.0, 0.0)
+            );
+            assert_eq!(explanation.filters, vec!["path contains \"src\""]);
+        }
+        let first = results[0].explanation.as_ref().unwrap();
+        let second = results[1].explanation.as_ref().unwrap();
+        assert!(first.bm25 < second.bm25);
 
-        // Insert a document
-        let doc = create_test_document("test1.txt", "This document will be deleted");
-        engine.insert_document(doc).unwrap();
-        assert_eq!(engine.stats().unwrap(), 1);
-
-        // Delete the document
-        let result = engine.delete_document("test1.txt");
-        assert!(result.is_ok());
-
-        // Should have 0 documents now
-        let count = engine.stats().unwrap();
-        assert_eq!(count, 0);
+        let results = engine
+            .search("rust", SearchType::FullText, None, None)
+            .unwrap();
+        assert!(results.iter().all(|result| result.explanation.is_none()));
     }
 
     #[test]
-    fn test_fulltext_search() {
+    fn test_find_similar() {
         let (engine, _temp_dir) = create_test_engine();
+        engine
+            .upsert_batch(
+                vec![
+                    create_test_document("a.md", "rust borrow checker"),
+                    create_test_document("b.md", "borrow checker errors"),
+                    create_test_document("c.md", "cooking pasta"),
+                ],
+                Some(vec![
+                    vec![1.0, 0.0, 0.0],
+                    vec![0.9, 0.436, 0.0],
+                    vec![0.0, 0.0, 1.0],
+                ]),
+            )
+            .unwrap();
+        // Indexed without an embedder, so it can only be compared by its words
+        engine
+            .insert_document(create_test_document("e.md", "rust tips"))
+            .unwrap();
+        let similar = |path: &str, terms: usize, options: &SearchOptions| {
+            engine
+                .find_similar(path, terms, options)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect::<Vec<_>>()
+        };
+        let options = SearchOptions::default();
 
-        // Insert test documents
-        let docs = vec![
-            create_test_document("rust1.txt", "Rust programming language is memory safe"),
-            create_test_document("python1.txt", "Python is a high-level programming language"),
-            create_test_document("js1.txt", "JavaScript is used for web development"),
-        ];
+        assert_eq!(similar("a.md", 0, &options), vec!["b.md"]);
+        assert_eq!(similar("a.md", 2, &options), vec!["b.md"]);
+        let filtered = SearchOptions {
+            path_filters: Some(vec!["c.md".to_string()]),
+            ..SearchOptions::default()
+        };
+        assert!(similar("a.md", 0, &filtered).is_empty());
+        assert_eq!(similar("e.md", 2, &options), vec!["a.md"]);
+        assert!(matches!(
+            engine.find_similar("e.md", 0, &options),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            engine.find_similar("missing.md", 0, &options),
+            Err(LocalSearchError::NotFound(_))
+        ));
+    }
 
-        for doc in docs {
-            engine.insert_document(doc).unwrap();
-        }
+    #[test]
+    fn test_search_by_vector_and_precomputed_embedding() {
+        let (engine, _temp_dir) = create_test_engine();
+        // Vectors from elsewhere are not normalized; a longer one must not rank higher
+        let mut document = create_test_document("a.md", "rust borrow checker");
+        document.embedding = Some(vec![2.0, 0.0, 0.0]);
+        engine.insert_document(document).unwrap();
+        let mut document = create_test_document("b.md", "cooking pasta");
+        document.embedding = Some(vec![0.0, 20.0, 0.0]);
+        engine.upsert_batch(vec![document], None).unwrap();
 
-        // Search for "rust"
         let results = engine
-            .search("programming", SearchType::FullText, Some(10), None)
+            .search_by_vector(&[9.0, 1.0, 0.0], &SearchOptions::default())
             .unwrap();
-        assert_eq!(results.len(), 2); // Should match rust1.txt
+        assert_eq!(results[0].path, "a.md");
+        assert!((results[0].final_score - 9.0 / 82f64.sqrt()).abs() < 1e-6);
+        assert!(results.iter().all(|r| r.final_score <= 1.0));
+        let filtered = SearchOptions {
+            path_filters: Some(vec!["b.md".to_string()]),
+            ..SearchOptions::default()
+        };
+        let results = engine
+            .search_by_vector(&[1.0, 9.0, 0.0], &filtered)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "b.md");
+
+        assert!(matches!(
+            engine.search_by_vector(&[1.0, 0.0], &SearchOptions::default()),
+            Err(LocalSearchError::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            engine.search_by_vector(&[], &SearchOptions::default()),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        let mut document = create_test_document("c.md", "wrong size");
+        document.embedding = Some(vec![1.0]);
+        assert!(matches!(
+            engine.insert_document(document),
+            Err(LocalSearchError::DimensionMismatch { .. })
+        ));
+    }
 
-        // All results should have FTS scores but no semantic scores
-        for result in &results {
-            println!(
-                "FTS Score: {:?}, Semantic Score: {:?}",
-                result.fts_score, result.semantic_score
-            );
-            assert!(result.fts_score.is_some() && result.fts_score.unwrap() > 0.1);
-            assert!(result.semantic_score.is_none());
-        }
+    #[test]
+    fn test_mmr_diversification() {
+        let (engine, _temp_dir) = create_test_engine();
+        engine
+            .upsert_batch(
+                vec![
+                    create_test_document("guide_v1.md", "install guide"),
+                    create_test_document("guide_v2.md", "install guide, revised"),
+                    create_test_document("faq.md", "install questions"),
+                ],
+                Some(vec![
+                    vec![0.0, 1.0, 0.0],
+                    vec![0.0, 0.995, 0.0998],
+                    vec![1.0, 0.0, 0.0],
+                ]),
+            )
+            .unwrap();
+        let query = [0.6, 0.8, 0.0];
+        let paths = |options: &SearchOptions| {
+            engine
+                .search_by_vector(&query, options)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect::<Vec<_>>()
+        };
+
+        let options = SearchOptions {
+            limit: Some(2),
+            ..SearchOptions::default()
+        };
+        assert_eq!(paths(&options), vec!["guide_v1.md", "guide_v2.md"]);
+        let diversified = SearchOptions {
+            mmr_lambda: Some(0.5),
+            explain: true,
+            ..options.clone()
+        };
+        assert_eq!(paths(&diversified), vec!["guide_v1.md", "faq.md"]);
+        let results = engine.search_by_vector(&query, &diversified).unwrap();
+        let redundancy = results[1].explanation.as_ref().unwrap().redundancy;
+        assert!(redundancy.is_some_and(|r| r.abs() < 1e-6));
+        let relevance_only = SearchOptions {
+            mmr_lambda: Some(1.0),
+            ..options.clone()
+        };
+        assert_eq!(paths(&relevance_only), vec!["guide_v1.md", "guide_v2.md"]);
+
+        let invalid = SearchOptions {
+            mmr_lambda: Some(1.5),
+            ..options
+        };
+        assert!(matches!(
+            engine.search_by_vector(&query, &invalid),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
     }
 
     #[test]
@@ -831,9 +2915,10 @@ mod tests {
         // Results should have both scores for documents that match both ways
         let mut found_both_scores = false;
         for result in &results {
-            if result.fts_score.is_some() && result.semantic_score.is_some() {
-                found_both_scores |=
-                    result.fts_score.unwrap() > 0.01 && result.semantic_score.unwrap() > 0.01;
+            if let (Some(fts_score), Some(semantic_score)) =
+                (result.fts_score, result.semantic_score)
+            {
+                found_both_scores |= fts_score > 0.01 && semantic_score > 0.01;
             }
             assert!(result.final_score > 0.0);
         }
@@ -877,7 +2962,7 @@ mod tests {
             engine.create_table().unwrap();
             let doc = create_test_document("test1.txt", "Test content");
             engine.insert_document(doc).unwrap();
-            assert_eq!(engine.stats().unwrap(), 1);
+            assert_eq!(engine.stats().unwrap().document_count, 1);
         } // engine goes out of scope, connection closed
 
         // Create new database file with different content
@@ -890,12 +2975,12 @@ mod tests {
             let doc2 = create_test_document("test3.txt", "More different content");
             new_engine.insert_document(doc1).unwrap();
             new_engine.insert_document(doc2).unwrap();
-            assert_eq!(new_engine.stats().unwrap(), 2);
+            assert_eq!(new_engine.stats().unwrap().document_count, 2);
             // Move new database file to original path
             std::fs::rename(temp_db_path, db_path).unwrap();
         } // new_engine goes out of scope
 
-        let count_before = engine.stats().unwrap();
+        let count_before = engine.stats().unwrap().document_count;
         assert_eq!(count_before, 1); // Should see the 2 documents from new database
 
         // Refresh connection
@@ -903,7 +2988,7 @@ mod tests {
         assert!(result.is_ok());
 
         // Should still see the same data after refresh
-        let count_after = engine.stats().unwrap();
+        let count_after = engine.stats().unwrap().document_count;
         assert_eq!(count_after, 2);
 
         // Verify specific documents exist
@@ -917,7 +3002,7 @@ mod tests {
     fn test_stats_empty_database() {
         let (engine, _temp_dir) = create_test_engine();
 
-        let count = engine.stats().unwrap();
+        let count = engine.stats().unwrap().document_count;
         assert_eq!(count, 0);
     }
 
@@ -934,7 +3019,10 @@ mod tests {
         // Semantic search should fail without embedder
         let semantic_result =
             engine.search("nonexistent query", SearchType::Semantic, Some(10), None);
-        assert!(semantic_result.is_err());
+        assert!(matches!(
+            semantic_result,
+            Err(LocalSearchError::MissingEmbedder(_))
+        ));
 
         // Hybrid should fallback to FTS without embedder
         let results = engine
@@ -960,6 +3048,17 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_create_table_keeps_recorded_model() {
+        let (engine, _temp_dir) = create_test_engine_with_embedder();
+        engine.set_meta(EMBEDDING_MODEL_KEY, "other-model").unwrap();
+        engine.create_table().unwrap();
+        assert_eq!(
+            engine.get_meta(EMBEDDING_MODEL_KEY).unwrap().as_deref(),
+            Some("other-model")
+        );
+    }
+
     #[test]
     fn test_duplicate_insertion_fails() {
         let (engine, _temp_dir) = create_test_engine();
@@ -984,7 +3083,7 @@ mod tests {
         let result = engine.delete_document("nonexistent.txt");
         assert!(result.is_ok());
 
-        let count = engine.stats().unwrap();
+        let count = engine.stats().unwrap().document_count;
         assert_eq!(count, 0);
     }
 
@@ -997,10 +3096,134 @@ mod tests {
         let result = engine.upsert_document(doc);
         assert!(result.is_ok());
 
-        let count = engine.stats().unwrap();
+        let count = engine.stats().unwrap().document_count;
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_get_document() {
+        let (engine, _temp_dir) = create_test_engine();
+        engine
+            .insert_document(create_test_document("notes/a.md", "Stored content"))
+            .unwrap();
+
+        let document = engine.get_document("notes/a.md").unwrap().unwrap();
+        assert_eq!(document.content, "Stored content");
+        assert_eq!(
+            document.metadata.unwrap().get("type").map(String::as_str),
+            Some("test")
+        );
+        assert!(document.created_at > 0.0);
+        assert_eq!(document.created_at, document.updated_at);
+
+        assert!(engine.get_document("missing.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_upsert_batch_stores_precomputed_embeddings() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        let docs = vec![
+            create_test_document("a.txt", "First document"),
+            create_test_document("b.txt", "Second document"),
+        ];
+        let embeddings = vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]];
+        engine.upsert_batch(docs, Some(embeddings)).unwrap();
+        assert_eq!(engine.stats().unwrap().document_count, 2);
+
+        // Stored embeddings are searchable even though the engine has no embedder
+        let results = engine
+            .search_by_embedding(
+                &engine.collection,
+                &[0.0, 1.0, 0.0],
+                &SearchOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "b.txt");
+
+        // A request's own embedding wins over the batch one
+        let mut doc = create_test_document("a.txt", "First document");
+        doc.embedding = Some(vec![0.0, 0.0, 1.0]);
+        engine
+            .upsert_batch(vec![doc], Some(vec![vec![1.0, 0.0, 0.0]]))
+            .unwrap();
+        let results = engine
+            .search_by_embedding(
+                &engine.collection,
+                &[0.0, 0.0, 1.0],
+                &SearchOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "a.txt");
+
+        // Mismatched embedding count is rejected without writing anything
+        let docs = vec![create_test_document("c.txt", "Third document")];
+        assert!(engine.upsert_batch(docs, Some(vec![])).is_err());
+        assert_eq!(engine.stats().unwrap().document_count, 2);
+
+        // Embeddings of another dimension than the stored ones are rejected
+        let docs = vec![create_test_document("c.txt", "Third document")];
+        assert!(matches!(
+            engine.upsert_batch(docs, Some(vec![vec![1.0, 0.0]])),
+            Err(LocalSearchError::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert_eq!(engine.stats().unwrap().document_count, 2);
+    }
+
+    #[test]
+    fn test_empty_embeddings_are_rejected() {
+        let (engine, _temp_dir) = create_test_engine();
+
+        let mut document = create_test_document("a.txt", "First document");
+        document.embedding = Some(vec![]);
+        assert!(matches!(
+            engine.insert_document(document),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        let docs = vec![create_test_document("b.txt", "Second document")];
+        assert!(matches!(
+            engine.upsert_batch(docs, Some(vec![vec![]])),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        assert_eq!(engine.stats().unwrap().document_count, 0);
+        assert_eq!(engine.stats().unwrap().embedding_dimension, None);
+    }
+
+    #[test]
+    fn test_precomputed_embedding_must_match_embedder() {
+        let (engine, _temp_dir) = create_test_engine_with_embedder();
+        let dimension = engine.embedder().unwrap().dimension().unwrap();
+
+        let mut document = create_test_document("a.txt", "First document");
+        document.embedding = Some(vec![0.5; dimension + 1]);
+        assert!(matches!(
+            engine.insert_document(document),
+            Err(LocalSearchError::DimensionMismatch { expected, .. }) if expected == dimension
+        ));
+        assert_eq!(engine.stats().unwrap().document_count, 0);
+    }
+
+    #[test]
+    fn test_computed_embeddings_must_match_stored() {
+        let (engine, temp_dir) = create_test_engine_with_embedder();
+        let db_path = temp_dir.path().join("test.db");
+        let other = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        let mut document = create_test_document("a.txt", "First document");
+        document.embedding = Some(vec![1.0, 0.0, 0.0]);
+        other.insert_document(document).unwrap();
+
+        assert!(matches!(
+            engine.upsert_document(create_test_document("b.txt", "Second document")),
+            Err(LocalSearchError::DimensionMismatch { expected: 3, .. })
+        ));
+        assert_eq!(engine.stats().unwrap().document_count, 1);
+    }
+
     #[test]
     fn test_softmax() {
         // Test basic softmax with different values
@@ -1142,4 +3365,374 @@ mod tests {
             .unwrap();
         assert_eq!(results_empty_filter.len(), 0); // Should match nothing
     }
+
+    #[test]
+    fn test_metadata_filter_functionality() {
+        let (engine, _temp_dir) = create_test_engine();
+        for (path, kind) in [("a.md", "note"), ("b.md", "ticket"), ("c.md", "note")] {
+            let mut doc = create_test_document(path, "shared search content");
+            doc.metadata
+                .as_mut()
+                .unwrap()
+                .insert("kind".to_string(), kind.to_string());
+            engine.insert_document(doc).unwrap();
+        }
+
+        let options = SearchOptions {
+            metadata_filters: Some(HashMap::from([("kind".to_string(), "note".to_string())])),
+            ..SearchOptions::default()
+        };
+        let mut paths: Vec<_> = engine
+            .search_with_options("content", SearchType::FullText, &options)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["a.md", "c.md"]);
+
+        let options = SearchOptions {
+            path_filters: Some(vec!["c.md".to_string()]),
+            metadata_filters: Some(HashMap::from([("kind".to_string(), "note".to_string())])),
+            limit: Some(5),
+            ..SearchOptions::default()
+        };
+        let results = engine
+            .search_with_options("content", SearchType::Hybrid, &options)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "c.md");
+    }
+
+    #[test]
+    fn test_list_documents_with_filters_and_pagination() {
+        let (engine, _temp_dir) = create_test_engine();
+        for path in ["notes/b.md", "notes/a.md", "notes%/c.md", "tickets/1.md"] {
+            engine
+                .insert_document(create_test_document(path, "content"))
+                .unwrap();
+        }
+
+        let paths = |options: &ListOptions| -> Vec<String> {
+            engine
+                .list_documents(options)
+                .unwrap()
+                .into_iter()
+                .map(|d| d.path)
+                .collect()
+        };
+
+        assert_eq!(paths(&ListOptions::default()).len(), 4);
+        let options = ListOptions {
+            path_prefix: Some("notes/".to_string()),
+            ..ListOptions::default()
+        };
+        assert_eq!(paths(&options), vec!["notes/a.md", "notes/b.md"]);
+
+        let options = ListOptions {
+            limit: Some(2),
+            offset: 1,
+            ..ListOptions::default()
+        };
+        assert_eq!(paths(&options), vec!["notes/a.md", "notes/b.md"]);
+
+        let options = ListOptions {
+            metadata_filters: Some(HashMap::from([(
+                "title".to_string(),
+                "Test Document tickets/1.md".to_string(),
+            )])),
+            ..ListOptions::default()
+        };
+        assert_eq!(paths(&options), vec!["tickets/1.md"]);
+    }
+
+    #[test]
+    fn test_delete_prefix_and_stats() {
+        let (engine, _temp_dir) = create_test_engine();
+        for path in ["notes/a.md", "notes/b.md", "tickets/1.md"] {
+            engine
+                .insert_document(create_test_document(path, "content"))
+                .unwrap();
+        }
+        engine
+            .upsert_batch(
+                vec![create_test_document("tickets/2.md", "content")],
+                Some(vec![vec![0.0, 1.0, 0.0]]),
+            )
+            .unwrap();
+
+        let stats = engine.stats().unwrap();
+        assert_eq!(stats.document_count, 4);
+        assert_eq!(stats.embedding_count, 1);
+        assert_eq!(stats.fts_row_count, 4);
+        assert!(stats.fts_integrity_ok);
+        assert_eq!(stats.embedding_dimension, Some(3));
+        assert_eq!(stats.documents_missing_embeddings, 3);
+        assert_eq!(stats.total_content_bytes, 4 * "content".len() as i64);
+        assert_eq!(stats.embedding_model, None);
+        assert!(stats.last_updated_at.unwrap() > 0.0);
+        assert!(stats.db_size_bytes > 0);
+        // Only some documents have embeddings, as after an interrupted ingestion
+        assert!(!stats.is_healthy());
+
+        assert_eq!(engine.delete_prefix("notes/").unwrap(), 2);
+        assert_eq!(engine.delete_prefix("tickets/2").unwrap(), 1);
+        let stats = engine.stats().unwrap();
+        assert_eq!(stats.document_count, 1);
+        assert_eq!(stats.fts_row_count, 1);
+        assert_eq!(stats.embedding_count, 0);
+        assert_eq!(stats.embedding_dimension, None);
+        assert!(stats.is_healthy());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        use crate::export::ExportFormat;
+
+        let (source, _source_dir) = create_test_engine();
+        source
+            .upsert_batch(
+                vec![
+                    create_test_document("a.md", "alpha content"),
+                    create_test_document("b.md", "beta content"),
+                ],
+                Some(vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]]),
+            )
+            .unwrap();
+        source.set_meta(EMBEDDING_MODEL_KEY, "test-model").unwrap();
+        let original = source.get_document("a.md").unwrap().unwrap();
+
+        for format in [ExportFormat::Jsonl, ExportFormat::Binary] {
+            let mut bytes = Vec::new();
+            let options = ExportOptions {
+                format,
+                include_embeddings: true,
+            };
+            assert_eq!(source.export_to(&mut bytes, &options).unwrap(), 2);
+
+            let (target, _target_dir) = create_test_engine();
+            let summary = target.import_from(bytes.as_slice()).unwrap();
+            assert_eq!(summary.document_count, 2);
+            assert_eq!(summary.reused_embeddings, 2);
+            assert_eq!(summary.computed_embeddings, 0);
+
+            let imported = target.get_document("a.md").unwrap().unwrap();
+            assert_eq!(imported.content, original.content);
+            assert_eq!(imported.metadata, original.metadata);
+            assert_eq!(imported.created_at, original.created_at);
+            assert_eq!(imported.updated_at, original.updated_at);
+
+            let stats = target.stats().unwrap();
+            assert_eq!(stats.embedding_model.as_deref(), Some("test-model"));
+            assert!(stats.is_healthy());
+            let results = target
+                .search_by_embedding(
+                    &target.collection,
+                    &[0.0, 1.0, 0.0],
+                    &SearchOptions::default(),
+                )
+                .unwrap();
+            assert_eq!(results[0].path, "b.md");
+            let results = target
+                .search("alpha", SearchType::FullText, Some(10), None)
+                .unwrap();
+            assert_eq!(results[0].path, "a.md");
+        }
+    }
+
+    #[test]
+    fn test_import_skips_embeddings_from_other_model() {
+        let (source, _source_dir) = create_test_engine();
+        source
+            .upsert_batch(
+                vec![create_test_document("a.md", "alpha content")],
+                Some(vec![vec![1.0, 0.0, 0.0]]),
+            )
+            .unwrap();
+        source.set_meta(EMBEDDING_MODEL_KEY, "model-a").unwrap();
+        let mut bytes = Vec::new();
+        let options = ExportOptions {
+            include_embeddings: true,
+            ..ExportOptions::default()
+        };
+        source.export_to(&mut bytes, &options).unwrap();
+
+        let (target, _target_dir) = create_test_engine();
+        target.set_meta(EMBEDDING_MODEL_KEY, "model-b").unwrap();
+        let summary = target.import_from(bytes.as_slice()).unwrap();
+        assert_eq!(summary.document_count, 1);
+        assert_eq!(summary.reused_embeddings, 0);
+        assert_eq!(target.stats().unwrap().embedding_count, 0);
+    }
+
+    #[test]
+    fn test_import_rejects_embeddings_of_other_dimension() {
+        let (source, _source_dir) = create_test_engine();
+        source
+            .upsert_batch(
+                vec![create_test_document("a.md", "alpha content")],
+                Some(vec![vec![1.0, 0.0, 0.0]]),
+            )
+            .unwrap();
+        let mut bytes = Vec::new();
+        let options = ExportOptions {
+            include_embeddings: true,
+            ..ExportOptions::default()
+        };
+        source.export_to(&mut bytes, &options).unwrap();
+
+        let (target, _target_dir) = create_test_engine();
+        target
+            .upsert_batch(
+                vec![create_test_document("b.md", "beta content")],
+                Some(vec![vec![1.0, 0.0]]),
+            )
+            .unwrap();
+        assert!(matches!(
+            target.import_from(bytes.as_slice()),
+            Err(LocalSearchError::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+        assert_eq!(target.stats().unwrap().document_count, 1);
+    }
+
+    #[test]
+    fn test_snapshot_is_isolated_from_writes() {
+        // WAL databases pin a read transaction, others are copied into memory
+        for journal_mode in ["wal", "delete"] {
+            let (engine, temp_dir) = create_test_engine();
+            engine
+                .conn
+                .query_row(
+                    &format!("PRAGMA journal_mode = {}", journal_mode),
+                    [],
+                    |row| row.get::<_, String>(0),
+                )
+                .unwrap();
+            engine
+                .insert_document(create_test_document("a.md", "alpha content"))
+                .unwrap();
+
+            let db_path = temp_dir.path().join("test.db");
+            let mut snapshot =
+                SqliteLocalSearchEngine::open_snapshot(db_path.to_str().unwrap(), None).unwrap();
+            assert!(snapshot.is_snapshot());
+
+            engine
+                .insert_document(create_test_document("b.md", "beta content"))
+                .unwrap();
+            let stats = snapshot.stats().unwrap();
+            assert_eq!(stats.document_count, 1);
+            assert!(stats.is_healthy());
+            assert!(
+                snapshot
+                    .search("beta", SearchType::FullText, Some(10), None)
+                    .unwrap()
+                    .is_empty()
+            );
+            assert!(
+                snapshot
+                    .insert_document(create_test_document("c.md", "gamma"))
+                    .is_err()
+            );
+
+            snapshot.refresh().unwrap();
+            assert_eq!(snapshot.stats().unwrap().document_count, 2);
+        }
+    }
+
+    #[test]
+    fn test_backup_to() {
+        let (engine, temp_dir) = create_test_engine();
+        engine
+            .insert_document(create_test_document("a.md", "alpha content"))
+            .unwrap();
+
+        let backup_path = temp_dir.path().join("backup.db");
+        engine.backup_to(&backup_path).unwrap();
+        assert!(engine.backup_to(temp_dir.path().join("test.db")).is_err());
+
+        let backup = SqliteLocalSearchEngine::new(backup_path.to_str().unwrap(), None).unwrap();
+        assert_eq!(backup.stats().unwrap().document_count, 1);
+        let results = backup
+            .search("alpha", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results[0].path, "a.md");
+    }
+
+    #[test]
+    fn test_collections_are_isolated() {
+        let (engine, temp_dir) = create_test_engine();
+        engine
+            .insert_document(create_test_document("a.md", "shared rust notes"))
+            .unwrap();
+        engine.create_collection("tickets").unwrap();
+        assert!(engine.create_collection("tickets").is_err());
+        assert!(engine.create_collection("Bad-Name").is_err());
+
+        let db_path = temp_dir.path().join("test.db");
+        let tickets = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None)
+            .unwrap()
+            .with_collection("tickets")
+            .unwrap();
+        assert_eq!(tickets.collection(), "tickets");
+        tickets
+            .insert_document(create_test_document("a.md", "rust ticket about crashes"))
+            .unwrap();
+        tickets
+            .insert_document(create_test_document("b.md", "another rust ticket"))
+            .unwrap();
+
+        // Same path, different collections
+        assert_eq!(
+            engine.get_document("a.md").unwrap().unwrap().content,
+            "shared rust notes"
+        );
+        assert_eq!(engine.stats().unwrap().document_count, 1);
+        assert_eq!(tickets.stats().unwrap().document_count, 2);
+
+        let results = engine
+            .search("rust", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].collection, DEFAULT_COLLECTION);
+
+        let options = SearchOptions {
+            collections: Some(vec!["*".to_string()]),
+            ..SearchOptions::default()
+        };
+        let results = engine
+            .search_with_options("rust", SearchType::FullText, &options)
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results.iter().filter(|r| r.collection == "tickets").count(),
+            2
+        );
+        let options = SearchOptions {
+            collections: Some(vec!["missing".to_string()]),
+            ..SearchOptions::default()
+        };
+        assert!(
+            engine
+                .search_with_options("rust", SearchType::FullText, &options)
+                .is_err()
+        );
+
+        let collections = engine.list_collections().unwrap();
+        let names: Vec<_> = collections.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec![DEFAULT_COLLECTION, "tickets"]);
+        assert_eq!(collections[1].document_count, 2);
+        assert!(collections[1].created_at.is_some());
+
+        assert!(engine.drop_collection(DEFAULT_COLLECTION).is_err());
+        engine.drop_collection("tickets").unwrap();
+        assert_eq!(engine.list_collections().unwrap().len(), 1);
+        assert!(matches!(
+            engine.drop_collection("tickets"),
+            Err(LocalSearchError::NotFound(_))
+        ));
+    }
 }
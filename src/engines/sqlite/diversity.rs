@@ -0,0 +1,112 @@
+//! Maximal marginal relevance (MMR) re-ranking of search results.
+
+use super::{Collection, SqliteLocalSearchEngine};
+use crate::error::{LocalSearchError, Result};
+use crate::traits::SearchResult;
+use log::debug;
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+
+/// Candidates re-ranked for each requested result.
+const CANDIDATES_PER_RESULT: usize = 4;
+
+impl SqliteLocalSearchEngine {
+    /// Picks `limit` of the best results one at a time, each maximizing
+    /// `lambda * relevance - (1 - lambda) * redundancy`. Relevance is the score divided
+    /// by the best one, and redundancy the highest cosine similarity of the stored
+    /// embeddings to the results already picked. Documents without an embedding are
+    /// never redundant. `results` must be sorted by score.
+    pub(super) fn diversify(
+        &self,
+        mut results: Vec<SearchResult>,
+        lambda: f64,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        if !(0.0..=1.0).contains(&lambda) {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "MMR lambda must be between 0 and 1, got {}",
+                lambda
+            )));
+        }
+        results.truncate(limit.saturating_mul(CANDIDATES_PER_RESULT));
+        let embeddings = self.result_embeddings(&results)?;
+        let best_score = results
+            .iter()
+            .map(|result| result.final_score)
+            .fold(0.0, f64::max);
+        let scale = if best_score > 0.0 { best_score } else { 1.0 };
+
+        // Highest similarity of each remaining candidate to the picked results
+        let mut redundancy = vec![0.0; results.len()];
+        let mut remaining: Vec<usize> = (0..results.len()).collect();
+        let mut picked = Vec::new();
+        while picked.len() < limit && !remaining.is_empty() {
+            let mmr =
+                |i: usize| lambda * results[i].final_score / scale - (1.0 - lambda) * redundancy[i];
+            // Ties go to the better ranked candidate
+            let (position, &next) = remaining
+                .iter()
+                .enumerate()
+                .reduce(|best, candidate| {
+                    if mmr(*candidate.1) > mmr(*best.1) {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+                .expect("candidates remain");
+            remaining.remove(position);
+            if let Some(explanation) = results[next].explanation.as_mut() {
+                explanation.redundancy = Some(redundancy[next]);
+            }
+            if let Some(picked_embedding) = &embeddings[next] {
+                for &i in &remaining {
+                    if let Some(embedding) = &embeddings[i] {
+                        let similarity = Self::cosine_similarity(picked_embedding, embedding);
+                        redundancy[i] = redundancy[i].max(similarity);
+                    }
+                }
+            }
+            picked.push(next);
+        }
+        debug!(
+            "MMR with lambda {} picked candidates {:?} of {}",
+            lambda,
+            picked,
+            results.len()
+        );
+
+        let mut results: Vec<Option<SearchResult>> = results.into_iter().map(Some).collect();
+        Ok(picked
+            .into_iter()
+            .filter_map(|i| results[i].take())
+            .collect())
+    }
+
+    /// Reads the stored embedding of each result, if it has one.
+    fn result_embeddings(&self, results: &[SearchResult]) -> Result<Vec<Option<Vec<f32>>>> {
+        let mut collections: HashMap<&str, Collection> = HashMap::new();
+        let mut embeddings = Vec::with_capacity(results.len());
+        for result in results {
+            if !collections.contains_key(result.collection.as_str()) {
+                collections.insert(&result.collection, Collection::new(&result.collection)?);
+            }
+            let collection = &collections[result.collection.as_str()];
+            let embedding = self
+                .conn
+                .query_row(
+                    &format!(
+                        "SELECT embedding FROM {} WHERE path = ?1",
+                        collection.embeddings
+                    ),
+                    rusqlite::params![result.path],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+                .map_err(|e| LocalSearchError::sqlite("Failed to read result embedding", e))?
+                .map(|bytes| Self::embedding_from_bytes(&bytes));
+            embeddings.push(embedding);
+        }
+        Ok(embeddings)
+    }
+}
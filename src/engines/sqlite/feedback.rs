@@ -0,0 +1,122 @@
+//! Distinctive terms of documents, for query expansion and similar documents.
+
+use super::{Collection, SqliteLocalSearchEngine};
+use crate::error::{LocalSearchError, Result};
+use crate::traits::{QueryExpansion, SearchOptions};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+
+/// Most frequent words of the feedback documents whose document frequency is looked up.
+const MAX_CANDIDATES: usize = 50;
+/// Shortest word added to a query.
+const MIN_WORD_LENGTH: usize = 3;
+
+impl SqliteLocalSearchEngine {
+    /// Picks the words of the top semantic matches for `query_embedding` that best
+    /// characterize them, see [`Self::distinctive_terms`]. Returns nothing for
+    /// collections embedded by another model.
+    pub(super) fn expansion_terms(
+        &self,
+        collection: &Collection,
+        query_embedding: &[f32],
+        expansion: &QueryExpansion,
+        exclude: &HashSet<String>,
+        options: &SearchOptions,
+    ) -> Result<Vec<String>> {
+        if expansion.documents == 0 || expansion.terms == 0 || !self.embedder_matches(collection)? {
+            return Ok(Vec::new());
+        }
+        let hits = self.search_by_embedding(collection, query_embedding, options)?;
+        let paths: Vec<&str> = hits
+            .iter()
+            .take(expansion.documents)
+            .map(|hit| hit.path.as_str())
+            .collect();
+        self.distinctive_terms(collection, &paths, expansion.terms, exclude)
+    }
+
+    /// Picks up to `count` words that are frequent in the documents at `paths` but
+    /// rare in the collection (tf-idf), skipping `exclude` (lower case).
+    pub(super) fn distinctive_terms(
+        &self,
+        collection: &Collection,
+        paths: &[&str],
+        count: usize,
+        exclude: &HashSet<String>,
+    ) -> Result<Vec<String>> {
+        let kind = self.tokenizer_of(collection)?.kind;
+
+        // Term frequency over all the documents, and number of them containing it
+        let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+        for path in paths {
+            let content: String = self
+                .conn
+                .query_row(
+                    &format!(
+                        "SELECT content FROM {} WHERE path = ?1",
+                        collection.documents
+                    ),
+                    rusqlite::params![path],
+                    |row| row.get(0),
+                )
+                .map_err(|e| LocalSearchError::sqlite("Failed to read document content", e))?;
+            let mut seen = HashSet::new();
+            for word in kind.words(&content) {
+                let word = word.to_lowercase();
+                if word.chars().count() < MIN_WORD_LENGTH
+                    || !word.chars().any(char::is_alphabetic)
+                    || exclude.contains(&word)
+                {
+                    continue;
+                }
+                let count = counts.entry(word.clone()).or_default();
+                count.0 += 1;
+                if seen.insert(word) {
+                    count.1 += 1;
+                }
+            }
+        }
+        let mut candidates: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+        candidates.sort_by(|a, b| (b.1.1, b.1.0, &a.0).cmp(&(a.1.1, a.1.0, &b.0)));
+        candidates.truncate(MAX_CANDIDATES);
+
+        let total: i64 = self
+            .conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {}", collection.fts),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| LocalSearchError::sqlite("Failed to count documents", e))?;
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT COUNT(*) FROM {fts} WHERE {fts} MATCH ?1",
+                fts = collection.fts
+            ))
+            .map_err(|e| LocalSearchError::sqlite("Failed to prepare frequency query", e))?;
+        let mut scored = Vec::new();
+        for (word, (frequency, _)) in candidates {
+            let documents: i64 = stmt
+                .query_row(rusqlite::params![format!("\"{}\"", word)], |row| row.get(0))
+                .map_err(|e| LocalSearchError::sqlite("Failed to read term frequency", e))?;
+            // Words in every document do not tell documents apart
+            if documents >= total {
+                continue;
+            }
+            let idf = ((total + 1) as f64 / documents.max(1) as f64).ln();
+            scored.push((word, frequency as f64 * idf));
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let terms: Vec<String> = scored
+            .into_iter()
+            .take(count)
+            .map(|(word, _)| word)
+            .collect();
+        debug!(
+            "Distinctive terms in collection '{}': {:?}",
+            collection.name, terms
+        );
+        Ok(terms)
+    }
+}
@@ -0,0 +1,197 @@
+//! Fields indexed by full-text search and their BM25 weights.
+
+use crate::error::{LocalSearchError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Field names that cannot be used for metadata keys: the built-in fields and the
+/// hidden columns of FTS5 tables.
+const RESERVED_NAMES: &[&str] = &["content", "path", "rank", "rowid"];
+
+/// A metadata value indexed as its own full-text field, named after its key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataField {
+    pub key: String,
+    pub weight: f64,
+}
+
+/// Which parts of a document full-text search indexes, and how much a match in each
+/// counts towards the BM25 score. By default only the content is indexed.
+///
+/// Every indexed field is matched by unscoped queries and can be searched alone with
+/// `field:term`; the field names are `content`, `path` and the metadata keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchFields {
+    pub content_weight: f64,
+    /// Weight of the tokens in the document path, or `None` to not index paths.
+    pub path_weight: Option<f64>,
+    pub metadata: Vec<MetadataField>,
+}
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        SearchFields {
+            content_weight: 1.0,
+            path_weight: None,
+            metadata: Vec::new(),
+        }
+    }
+}
+
+impl SearchFields {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_content_weight(mut self, weight: f64) -> Self {
+        self.content_weight = weight;
+        self
+    }
+
+    /// Indexes the words of document paths, e.g. `docs` and `guide` in `docs/guide.md`.
+    pub fn with_path(mut self, weight: f64) -> Self {
+        self.path_weight = Some(weight);
+        self
+    }
+
+    /// Indexes the metadata value under `key`, replacing an earlier weight for it.
+    pub fn with_metadata(mut self, key: &str, weight: f64) -> Self {
+        self.metadata.retain(|field| field.key != key);
+        self.metadata.push(MetadataField {
+            key: key.to_string(),
+            weight,
+        });
+        self
+    }
+
+    /// Names of the indexed fields, usable as `name:term` in queries.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = vec!["content"];
+        if self.path_weight.is_some() {
+            names.push("path");
+        }
+        names.extend(self.metadata.iter().map(|field| field.key.as_str()));
+        names
+    }
+
+    /// Checks that metadata keys can be used as field names and weights are valid.
+    pub fn validate(&self) -> Result<()> {
+        let weights = std::iter::once(self.content_weight)
+            .chain(self.path_weight)
+            .chain(self.metadata.iter().map(|field| field.weight));
+        for weight in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Invalid field weight {}; weights must be zero or positive",
+                    weight
+                )));
+            }
+        }
+        for (i, field) in self.metadata.iter().enumerate() {
+            // Keys become FTS column names and appear unquoted in SQL
+            let valid = field.key.len() <= 64
+                && field.key.starts_with(|c: char| c.is_ascii_lowercase())
+                && field
+                    .key
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid || RESERVED_NAMES.contains(&field.key.as_str()) {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Invalid metadata field '{}'. Use lowercase letters, digits and '_', starting with a letter, other than {}.",
+                    field.key,
+                    RESERVED_NAMES.join(", ")
+                )));
+            }
+            if self.metadata[..i]
+                .iter()
+                .any(|other| other.key == field.key)
+            {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Metadata field '{}' is listed twice",
+                    field.key
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Column definitions of the FTS5 table. The path column always comes first so
+    /// rows can be joined to documents; it is only tokenized when paths are indexed.
+    pub(crate) fn fts_columns(&self) -> String {
+        let mut columns = vec![
+            if self.path_weight.is_some() {
+                "path".to_string()
+            } else {
+                "path UNINDEXED".to_string()
+            },
+            "content".to_string(),
+        ];
+        columns.extend(self.metadata.iter().map(|field| field.key.clone()));
+        columns.join(", ")
+    }
+
+    /// Arguments of `bm25()` after the table name, one weight per FTS column.
+    pub(crate) fn bm25_weights(&self) -> String {
+        let mut weights = vec![self.path_weight.unwrap_or(0.0), self.content_weight];
+        weights.extend(self.metadata.iter().map(|field| field.weight));
+        weights
+            .iter()
+            .map(|weight| weight.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Statement copying the documents matching `condition` (on alias `d`) into the
+    /// FTS table, with metadata fields extracted from the stored JSON.
+    pub(crate) fn fts_insert_sql(&self, fts: &str, documents: &str, condition: &str) -> String {
+        let mut columns = vec!["path".to_string(), "content".to_string()];
+        let mut values = vec!["d.path".to_string(), "d.content".to_string()];
+        for field in &self.metadata {
+            columns.push(field.key.clone());
+            values.push(format!(
+                "COALESCE(json_extract(d.metadata, '$.\"{}\"'), '')",
+                field.key
+            ));
+        }
+        format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {} d{}",
+            fts,
+            columns.join(", "),
+            values.join(", "),
+            documents,
+            condition
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_validation_and_sql() {
+        let fields = SearchFields::new()
+            .with_path(0.5)
+            .with_metadata("title", 3.0)
+            .with_metadata("title", 4.0);
+        assert!(fields.validate().is_ok());
+        assert_eq!(fields.names(), vec!["content", "path", "title"]);
+        assert_eq!(fields.fts_columns(), "path, content, title");
+        assert_eq!(fields.bm25_weights(), "0.5, 1, 4");
+        assert_eq!(
+            SearchFields::default().fts_columns(),
+            "path UNINDEXED, content"
+        );
+
+        for invalid in [
+            SearchFields::new().with_metadata("path", 1.0),
+            SearchFields::new().with_metadata("Title", 1.0),
+            SearchFields::new().with_metadata("a'b", 1.0),
+            SearchFields::new().with_content_weight(-1.0),
+        ] {
+            assert!(matches!(
+                invalid.validate(),
+                Err(LocalSearchError::InvalidArgument(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Typo tolerance: spelling candidates for query words from the full-text vocabulary.
+
+use super::{Collection, SqliteLocalSearchEngine, TokenizerKind};
+use crate::error::{LocalSearchError, Result};
+use crate::query::QueryNode;
+use log::debug;
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+
+/// Number of corrections a misspelt word is expanded to.
+pub(super) const MAX_EXPANSIONS: usize = 3;
+/// Shortest stemmed term compared with the start of a longer query word.
+const MIN_STEM_LENGTH: usize = 4;
+
+/// An index term close to a query word that does not occur in the index.
+#[derive(Debug, Clone)]
+pub(super) struct Candidate {
+    /// The term as written in a document, or the index term if none was found.
+    pub(super) word: String,
+    pub(super) distance: usize,
+    /// Number of documents containing the term.
+    pub(super) documents: i64,
+}
+
+/// Best correction of each query word for a "did you mean" suggestion. `None` marks
+/// a word that occurs in some collection and must not be corrected.
+pub(super) type Suggestions = HashMap<String, Option<Candidate>>;
+
+/// Edits allowed for a word: none for short words, where almost any change yields
+/// another real word, one up to 7 characters and two beyond.
+fn max_edits(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions and
+/// transpositions of adjacent characters each count as one edit.
+pub(super) fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Words of `node` that spelling correction applies to: plain terms made of letters
+/// and digits. Prefixes, phrases, `NEAR` groups and excluded terms are left alone.
+pub(super) fn correctable_words(node: &QueryNode) -> Vec<&str> {
+    match node {
+        QueryNode::Term { text, prefix } => {
+            let correctable = !prefix
+                && text.chars().all(char::is_alphanumeric)
+                && text.chars().any(char::is_alphabetic);
+            if correctable {
+                vec![text.as_str()]
+            } else {
+                Vec::new()
+            }
+        }
+        QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+            nodes.iter().flat_map(correctable_words).collect()
+        }
+        QueryNode::Not(left, _) => correctable_words(left),
+        QueryNode::Field { node, .. } => correctable_words(node),
+        QueryNode::Phrase { .. } | QueryNode::Near { .. } => Vec::new(),
+    }
+}
+
+/// Replaces each corrected term with an `OR` of the term and its corrections.
+pub(super) fn expand_terms(
+    node: QueryNode,
+    corrections: &HashMap<String, Vec<String>>,
+) -> QueryNode {
+    match node {
+        QueryNode::Term {
+            ref text,
+            prefix: false,
+        } if corrections.contains_key(text) => {
+            let mut terms = vec![node.clone()];
+            terms.extend(corrections[text].iter().map(|word| QueryNode::Term {
+                text: word.clone(),
+                prefix: false,
+            }));
+            QueryNode::Or(terms)
+        }
+        QueryNode::And(nodes) => QueryNode::And(
+            nodes
+                .into_iter()
+                .map(|node| expand_terms(node, corrections))
+                .collect(),
+        ),
+        QueryNode::Or(nodes) => QueryNode::Or(
+            nodes
+                .into_iter()
+                .map(|node| expand_terms(node, corrections))
+                .collect(),
+        ),
+        QueryNode::Not(left, right) => {
+            QueryNode::Not(Box::new(expand_terms(*left, corrections)), right)
+        }
+        QueryNode::Field { name, node } => QueryNode::Field {
+            name,
+            node: Box::new(expand_terms(*node, corrections)),
+        },
+        node => node,
+    }
+}
+
+/// Replaces the words of `query` that have a correction, or returns `None` if none
+/// has one.
+pub(super) fn suggestion(query: &str, corrections: &Suggestions) -> Option<String> {
+    let mut changed = false;
+    let suggestion = query
+        .split_whitespace()
+        .map(|token| {
+            // Keep a `field:` scope in front of the corrected word
+            let (scope, word) = match token.split_once(':') {
+                Some((field, word)) => (&token[..field.len() + 1], word),
+                None => ("", token),
+            };
+            match corrections.get(word) {
+                Some(Some(candidate)) => {
+                    changed = true;
+                    format!("{}{}", scope, candidate.word)
+                }
+                _ => token.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    debug!("Suggestion for query '{}': {:?}", query, suggestion);
+    changed.then_some(suggestion)
+}
+
+impl SqliteLocalSearchEngine {
+    /// Adds the best correction in a collection of each correctable word of `node`,
+    /// keeping the closest and most common one across collections.
+    pub(super) fn collect_suggestions(
+        &self,
+        collection: &Collection,
+        node: &QueryNode,
+        corrections: &mut Suggestions,
+    ) -> Result<()> {
+        for word in correctable_words(node) {
+            let best = match self.spelling_candidates(collection, word)? {
+                None => {
+                    corrections.insert(word.to_string(), None);
+                    continue;
+                }
+                Some(candidates) => candidates.into_iter().next(),
+            };
+            let entry = corrections
+                .entry(word.to_string())
+                .or_insert_with(|| best.clone());
+            if let (Some(current), Some(best)) = (entry.as_mut(), best)
+                && (best.distance, -best.documents) < (current.distance, -current.documents)
+            {
+                *current = best;
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of the `fts5vocab` table listing the terms of a full-text index.
+    pub(super) fn vocabulary_table(collection: &Collection) -> String {
+        format!("{}_vocab", collection.fts)
+    }
+
+    /// Name of the `fts5vocab` table listing where each index term occurs.
+    pub(super) fn instances_table(collection: &Collection) -> String {
+        format!("{}_instances", collection.fts)
+    }
+
+    /// Returns `None` if `word` occurs in the collection, otherwise index terms within
+    /// a few edits of it, closest and most common first. Trigram indexes already match
+    /// parts of words and have no word vocabulary, so they get no candidates.
+    pub(super) fn spelling_candidates(
+        &self,
+        collection: &Collection,
+        word: &str,
+    ) -> Result<Option<Vec<Candidate>>> {
+        let known: Option<i64> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT 1 FROM {fts} WHERE {fts} MATCH ?1 LIMIT 1",
+                    fts = collection.fts
+                ),
+                rusqlite::params![format!("\"{}\"", word)],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to look up query word", e))?;
+        if known.is_some() {
+            return Ok(None);
+        }
+
+        let word = word.to_lowercase();
+        let length = word.chars().count();
+        let max_edits = max_edits(length);
+        let vocabulary = Self::vocabulary_table(collection);
+        let tokenizer = self.tokenizer_of(collection)?;
+        if max_edits == 0
+            || tokenizer.kind == TokenizerKind::Trigram
+            || !self.table_exists(&vocabulary)?
+        {
+            return Ok(Some(Vec::new()));
+        }
+        // Stemmed terms are mostly prefixes of the words they come from, so shorter
+        // terms are also compared with the start of the word
+        let min_length = if tokenizer.porter {
+            MIN_STEM_LENGTH.min(length)
+        } else {
+            length - max_edits
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT term, doc FROM {} WHERE length(term) BETWEEN ?1 AND ?2",
+                vocabulary
+            ))
+            .map_err(|e| LocalSearchError::sqlite("Failed to read index vocabulary", e))?;
+        let terms = stmt
+            .query_map(
+                rusqlite::params![min_length as i64, (length + max_edits) as i64],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| LocalSearchError::sqlite("Failed to read index vocabulary", e))?;
+        let mut candidates: Vec<Candidate> = terms
+            .into_iter()
+            .filter_map(|(term, documents)| {
+                let distance = match edit_distance(&word, &term) {
+                    distance if distance <= max_edits => distance,
+                    _ if tokenizer.porter => {
+                        let term_length = term.chars().count();
+                        let start: String = word.chars().take(term_length).collect();
+                        Some(edit_distance(&start, &term)).filter(|&distance| {
+                            term_length < length && distance <= self::max_edits(term_length)
+                        })?
+                    }
+                    _ => return None,
+                };
+                Some(Candidate {
+                    word: term,
+                    distance,
+                    documents,
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(b.documents.cmp(&a.documents))
+                .then(a.word.cmp(&b.word))
+        });
+        candidates.truncate(MAX_EXPANSIONS);
+        for candidate in &mut candidates {
+            if let Some(word) = self.surface_form(collection, tokenizer.kind, &candidate.word)? {
+                candidate.word = word;
+            }
+        }
+        debug!("Spelling candidates for '{}': {:?}", word, candidates);
+        Ok(Some(candidates))
+    }
+
+    /// Index terms may be stemmed or stripped of accents, and searching for a stem
+    /// does not always find it again; this reads the word at the term's first
+    /// position in the content instead.
+    fn surface_form(
+        &self,
+        collection: &Collection,
+        kind: TokenizerKind,
+        term: &str,
+    ) -> Result<Option<String>> {
+        let instances = Self::instances_table(collection);
+        if !self.table_exists(&instances)? {
+            return Ok(None);
+        }
+        let occurrence: Option<(i64, String)> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT i.offset, f.content FROM {} i JOIN {fts} f ON f.rowid = i.doc
+                     WHERE i.term = ?1 AND i.col = 'content' LIMIT 1",
+                    instances,
+                    fts = collection.fts
+                ),
+                rusqlite::params![term],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to look up index term", e))?;
+        Ok(occurrence.and_then(|(offset, content)| {
+            kind.words(&content)
+                .nth(offset as usize)
+                .map(str::to_lowercase)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{QuerySyntax, parse_query};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("embeding", "embedding"), 1);
+        assert_eq!(edit_distance("lnaguage", "language"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("münchen", "munchen"), 1);
+    }
+
+    #[test]
+    fn test_expand_terms() {
+        let node = parse_query(
+            "embeding NOT rust* \"a phrase\"",
+            QuerySyntax::Advanced,
+            &[],
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(correctable_words(&node), vec!["embeding"]);
+        let corrections = HashMap::from([("embeding".to_string(), vec!["embedding".to_string()])]);
+        assert_eq!(
+            expand_terms(node, &corrections).to_fts(),
+            "((\"embeding\" OR \"embedding\") NOT \"rust\"*) AND \"a phrase\""
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Thread-safe engine handle backed by a small SQLite connection pool.
+
+use super::SqliteLocalSearchEngine;
+use crate::LocalEmbedder;
+use crate::error::{LocalSearchError, Result};
+use crate::traits::{
+    DocumentIndexer, DocumentInfo, DocumentRequest, IndexStats, ListOptions, LocalSearch,
+    QueryExplanation, SearchOptions, SearchOutcome, SearchResult, SearchType, StoredDocument,
+};
+use log::{debug, info};
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How long a connection waits for a lock held by another connection.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [`SqliteLocalSearchEngine`] that can be cloned and shared across threads.
+///
+/// The database is switched to WAL mode so that one writer and many readers can work
+/// at the same time: writes are serialised through a single connection, while
+/// searches and other reads use a pool of read-only connections and never wait for a
+/// write to finish. The embedder is shared by all connections.
+#[derive(Clone)]
+pub struct SharedSqliteEngine {
+    inner: Arc<Pool>,
+}
+
+struct Pool {
+    writer: Mutex<SqliteLocalSearchEngine>,
+    readers: Mutex<Readers>,
+    reader_returned: Condvar,
+    max_readers: usize,
+    template: ReaderTemplate,
+}
+
+/// Idle reader connections and the number currently open.
+struct Readers {
+    idle: Vec<SqliteLocalSearchEngine>,
+    open: usize,
+}
+
+/// What a new reader connection is opened with, copied from the writer.
+struct ReaderTemplate {
+    db_path: String,
+    embedder: Option<Arc<LocalEmbedder>>,
+    collection: super::Collection,
+}
+
+impl SharedSqliteEngine {
+    /// Wraps `engine` as the writer and allows up to `max_readers` reader connections.
+    /// Create tables and pick a collection on `engine` before wrapping it.
+    pub fn new(engine: SqliteLocalSearchEngine, max_readers: usize) -> Result<Self> {
+        if engine.snapshot {
+            return Err(LocalSearchError::InvalidArgument(
+                "A read-only snapshot cannot be shared for writing".to_string(),
+            ));
+        }
+        engine
+            .conn
+            .busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| LocalSearchError::sqlite("Failed to set busy timeout", e))?;
+        let journal_mode: String = engine
+            .conn
+            .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+            .map_err(|e| LocalSearchError::sqlite("Failed to enable WAL mode", e))?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "Database {} does not support WAL mode (journal mode is {})",
+                engine.db_path, journal_mode
+            )));
+        }
+
+        let template = ReaderTemplate {
+            db_path: engine.db_path.clone(),
+            embedder: engine.embedder.clone(),
+            collection: engine.collection.clone(),
+        };
+        info!(
+            "Sharing engine for {} with up to {} readers",
+            template.db_path, max_readers
+        );
+        Ok(SharedSqliteEngine {
+            inner: Arc::new(Pool {
+                writer: Mutex::new(engine),
+                readers: Mutex::new(Readers {
+                    idle: Vec::new(),
+                    open: 0,
+                }),
+                reader_returned: Condvar::new(),
+                max_readers: max_readers.max(1),
+                template,
+            }),
+        })
+    }
+
+    /// Returns the name of the collection this handle works on.
+    pub fn collection(&self) -> &str {
+        &self.inner.template.collection.name
+    }
+
+    /// Locks the writer connection. Other writes wait; reads do not.
+    fn writer(&self) -> Result<MutexGuard<'_, SqliteLocalSearchEngine>> {
+        self.inner
+            .writer
+            .lock()
+            .map_err(|_| LocalSearchError::Panicked("Writer connection lock poisoned".to_string()))
+    }
+
+    /// Takes an idle reader, opens a new one if the pool is not full, or waits for one
+    /// to be returned.
+    fn reader(&self) -> Result<PooledReader<'_>> {
+        let pool = &self.inner;
+        let mut readers = pool
+            .readers
+            .lock()
+            .map_err(|_| LocalSearchError::Panicked("Reader pool lock poisoned".to_string()))?;
+        loop {
+            if let Some(engine) = readers.idle.pop() {
+                return Ok(PooledReader {
+                    pool,
+                    engine: Some(engine),
+                });
+            }
+            if readers.open < pool.max_readers {
+                readers.open += 1;
+                drop(readers);
+                return match pool.template.open() {
+                    Ok(engine) => Ok(PooledReader {
+                        pool,
+                        engine: Some(engine),
+                    }),
+                    Err(e) => {
+                        if let Ok(mut readers) = pool.readers.lock() {
+                            readers.open -= 1;
+                        }
+                        Err(e)
+                    }
+                };
+            }
+            readers = pool
+                .reader_returned
+                .wait(readers)
+                .map_err(|_| LocalSearchError::Panicked("Reader pool lock poisoned".to_string()))?;
+        }
+    }
+}
+
+impl ReaderTemplate {
+    fn open(&self) -> Result<SqliteLocalSearchEngine> {
+        let conn = Connection::open_with_flags(
+            &self.db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|e| LocalSearchError::sqlite("Failed to open reader connection", e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| LocalSearchError::sqlite("Failed to set busy timeout", e))?;
+        debug!("Opened reader connection for {}", self.db_path);
+        Ok(SqliteLocalSearchEngine {
+            db_path: self.db_path.clone(),
+            conn,
+            embedder: self.embedder.clone(),
+            snapshot: false,
+            collection: self.collection.clone(),
+            tokenizer: None,
+        })
+    }
+}
+
+/// A reader connection borrowed from the pool and returned on drop.
+struct PooledReader<'a> {
+    pool: &'a Pool,
+    engine: Option<SqliteLocalSearchEngine>,
+}
+
+impl Deref for PooledReader<'_> {
+    type Target = SqliteLocalSearchEngine;
+
+    fn deref(&self) -> &SqliteLocalSearchEngine {
+        self.engine
+            .as_ref()
+            .expect("reader is present until dropped")
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        if let (Some(engine), Ok(mut readers)) = (self.engine.take(), self.pool.readers.lock()) {
+            readers.idle.push(engine);
+            self.pool.reader_returned.notify_one();
+        }
+    }
+}
+
+impl DocumentIndexer for SharedSqliteEngine {
+    fn insert_document(&self, request: DocumentRequest) -> Result<()> {
+        self.writer()?.insert_document(request)
+    }
+
+    fn upsert_document(&self, request: DocumentRequest) -> Result<()> {
+        self.writer()?.upsert_document(request)
+    }
+
+    fn delete_document(&self, path: &str) -> Result<()> {
+        self.writer()?.delete_document(path)
+    }
+
+    fn get_document(&self, path: &str) -> Result<Option<StoredDocument>> {
+        self.reader()?.get_document(path)
+    }
+
+    fn list_documents(&self, options: &ListOptions) -> Result<Vec<DocumentInfo>> {
+        self.reader()?.list_documents(options)
+    }
+
+    fn delete_prefix(&self, prefix: &str) -> Result<usize> {
+        self.writer()?.delete_prefix(prefix)
+    }
+
+    fn stats(&self) -> Result<IndexStats> {
+        self.reader()?.stats()
+    }
+
+    /// Reopens the writer and closes idle readers; new readers are opened on demand.
+    fn refresh(&mut self) -> Result<()> {
+        self.writer()?.refresh()?;
+        let mut readers = self
+            .inner
+            .readers
+            .lock()
+            .map_err(|_| LocalSearchError::Panicked("Reader pool lock poisoned".to_string()))?;
+        readers.open -= readers.idle.len();
+        readers.idle.clear();
+        Ok(())
+    }
+
+    fn embedder(&self) -> Option<&LocalEmbedder> {
+        self.inner.template.embedder.as_deref()
+    }
+
+    fn upsert_batch(
+        &self,
+        requests: Vec<DocumentRequest>,
+        embeddings: Option<Vec<Vec<f32>>>,
+    ) -> Result<()> {
+        self.writer()?.upsert_batch(requests, embeddings)
+    }
+}
+
+impl LocalSearch for SharedSqliteEngine {
+    fn search(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        top: Option<i8>,
+        path_filters: Option<&[String]>,
+    ) -> Result<Vec<SearchResult>> {
+        self.reader()?.search(query, search_type, top, path_filters)
+    }
+
+    fn search_with_options(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.reader()?
+            .search_with_options(query, search_type, options)
+    }
+
+    fn search_with_details(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        self.reader()?
+            .search_with_details(query, search_type, options)
+    }
+
+    fn search_by_vector(
+        &self,
+        embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.reader()?.search_by_vector(embedding, options)
+    }
+
+    fn find_similar(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.reader()?.find_similar(path, terms, options)
+    }
+
+    fn suggest(&self, query: &str, options: &SearchOptions) -> Result<Option<String>> {
+        self.reader()?.suggest(query, options)
+    }
+
+    fn explain_query(&self, query: &str, options: &SearchOptions) -> Result<Vec<QueryExplanation>> {
+        self.reader()?.explain_query(query, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tempfile::TempDir;
+
+    fn create_shared_engine(max_readers: usize) -> (SharedSqliteEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        engine
+            .insert_document(document("seed.md", "seed document about rust"))
+            .unwrap();
+        (
+            SharedSqliteEngine::new(engine, max_readers).unwrap(),
+            temp_dir,
+        )
+    }
+
+    fn document(path: &str, content: &str) -> DocumentRequest {
+        DocumentRequest {
+            path: path.to_string(),
+            content: content.to_string(),
+            metadata: None,
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn test_shared_engine_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone + 'static>() {}
+        assert_send_sync::<SharedSqliteEngine>();
+    }
+
+    #[test]
+    fn test_reads_proceed_during_open_write_transaction() {
+        let (engine, _temp_dir) = create_shared_engine(2);
+        {
+            // Hold the writer in the middle of an uncommitted transaction
+            let writer = engine.writer().unwrap();
+            writer.conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+            writer
+                .insert_document(document("pending.md", "pending rust change"))
+                .unwrap();
+
+            let reader = engine.clone();
+            let results = std::thread::spawn(move || {
+                reader.search("rust", SearchType::FullText, Some(10), None)
+            })
+            .join()
+            .unwrap()
+            .unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].path, "seed.md");
+
+            writer.conn.execute_batch("COMMIT").unwrap();
+        }
+        assert_eq!(engine.stats().unwrap().document_count, 2);
+    }
+
+    #[test]
+    fn test_concurrent_searches_and_writes() {
+        let (engine, _temp_dir) = create_shared_engine(3);
+        let writing = Arc::new(AtomicBool::new(true));
+        let start = Arc::new(Barrier::new(4));
+
+        let writer = {
+            let (engine, writing, start) = (engine.clone(), writing.clone(), start.clone());
+            std::thread::spawn(move || {
+                start.wait();
+                for batch in 0..20 {
+                    let documents = (0..10)
+                        .map(|i| document(&format!("{}-{}.md", batch, i), "batch rust content"))
+                        .collect();
+                    engine.upsert_batch(documents, None).unwrap();
+                }
+                writing.store(false, Ordering::SeqCst);
+            })
+        };
+        let searchers: Vec<_> = (0..3)
+            .map(|_| {
+                let (engine, writing, start) = (engine.clone(), writing.clone(), start.clone());
+                std::thread::spawn(move || {
+                    start.wait();
+                    let mut searches = 0;
+                    while writing.load(Ordering::SeqCst) || searches == 0 {
+                        let results = engine
+                            .search("rust", SearchType::FullText, Some(5), None)
+                            .unwrap();
+                        assert!(!results.is_empty());
+                        searches += 1;
+                    }
+                    searches
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for searcher in searchers {
+            assert!(searcher.join().unwrap() > 0);
+        }
+        assert_eq!(engine.stats().unwrap().document_count, 201);
+        assert!(engine.inner.readers.lock().unwrap().open <= 3);
+    }
+}
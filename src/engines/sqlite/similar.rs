@@ -0,0 +1,91 @@
+//! More-like-this search from a stored document.
+
+use super::SqliteLocalSearchEngine;
+use crate::error::{LocalSearchError, Result};
+use crate::query::QueryNode;
+use crate::traits::{DocumentIndexer, SearchOptions, SearchResult};
+use log::debug;
+use rusqlite::OptionalExtension;
+use std::collections::HashSet;
+
+impl SqliteLocalSearchEngine {
+    /// Ranks documents by similarity to the document at `path` in the engine's
+    /// collection, using its stored embedding and, if `terms` is not zero, a
+    /// full-text query of its most distinctive words. The document is not re-embedded.
+    pub(super) fn similar_documents(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let source = &self.collection;
+        if self.get_document(path)?.is_none() {
+            return Err(LocalSearchError::NotFound(format!("Document '{}'", path)));
+        }
+        let embedding: Option<Vec<f32>> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT embedding FROM {} WHERE path = ?1",
+                    source.embeddings
+                ),
+                rusqlite::params![path],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()
+            .map_err(|e| LocalSearchError::sqlite("Failed to read document embedding", e))?
+            .map(|bytes| Self::embedding_from_bytes(&bytes));
+        if embedding.is_none() && terms == 0 {
+            return Err(LocalSearchError::InvalidArgument(format!(
+                "Document '{}' has no embedding; match its terms instead",
+                path
+            )));
+        }
+
+        let words = if terms > 0 {
+            self.distinctive_terms(source, &[path], terms, &HashSet::new())?
+        } else {
+            Vec::new()
+        };
+        let fts_query = (!words.is_empty()).then(|| {
+            QueryNode::Or(
+                words
+                    .iter()
+                    .map(|word| QueryNode::Term {
+                        text: word.clone(),
+                        prefix: false,
+                    })
+                    .collect(),
+            )
+            .to_fts()
+        });
+        debug!(
+            "Finding documents similar to '{}' with {:?}",
+            path, fts_query
+        );
+
+        let source_model = self.get_meta(&source.model_key)?;
+        let collections = self.search_collections(options)?;
+        let mut results = Vec::new();
+        for collection in &collections {
+            // Vectors of different models are not comparable
+            let embedding = match (&source_model, self.get_meta(&collection.model_key)?) {
+                (Some(source_model), Some(model)) if *source_model != model => None,
+                _ => embedding.as_deref(),
+            };
+            let found = match embedding {
+                Some(embedding) if fts_query.is_some() => {
+                    self.hybrid_ranking(collection, fts_query.as_deref(), embedding, options)?
+                }
+                Some(embedding) => self.semantic_ranking(collection, embedding, options)?,
+                None => self.search_fulltext_only(collection, fts_query.as_deref(), options)?,
+            };
+            results.extend(
+                found
+                    .into_iter()
+                    .filter(|result| result.collection != source.name || result.path != path),
+            );
+        }
+        self.finish_results(results, collections.len() > 1, options)
+    }
+}
@@ -0,0 +1,221 @@
+//! Synonym groups applied to full-text queries.
+
+use crate::error::{LocalSearchError, Result};
+use crate::query::QueryNode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Groups of interchangeable terms, such as `k8s, kubernetes`. A query word matching
+/// any entry of a group also matches the other entries; entries of several words
+/// match as phrases, e.g. `ml, machine learning`.
+///
+/// Synonyms are applied when searching, so changing them needs no reindexing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Synonyms {
+    pub groups: Vec<Vec<String>>,
+}
+
+impl Synonyms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a group of terms that are interchangeable in queries.
+    pub fn with_group(mut self, terms: &[&str]) -> Self {
+        self.groups
+            .push(terms.iter().map(|term| term.to_string()).collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Checks that every group has at least two entries and no entry is empty.
+    pub fn validate(&self) -> Result<()> {
+        for group in &self.groups {
+            if group.len() < 2 {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Synonym group [{}] needs at least two terms",
+                    group.join(", ")
+                )));
+            }
+            if group.iter().any(|term| normalize(term).is_empty()) {
+                return Err(LocalSearchError::InvalidArgument(format!(
+                    "Synonym group [{}] contains an empty term",
+                    group.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Entries interchangeable with the single query word `word`, excluding itself.
+    fn synonyms_of(&self, word: &str) -> Vec<&str> {
+        let word = normalize(word);
+        let mut synonyms = Vec::new();
+        for group in &self.groups {
+            if group.iter().any(|term| normalize(term) == word) {
+                for term in group {
+                    if normalize(term) != word && !synonyms.contains(&term.as_str()) {
+                        synonyms.push(term.as_str());
+                    }
+                }
+            }
+        }
+        synonyms
+    }
+
+    /// Replaces each plain term and phrase that has synonyms with an `OR` of itself
+    /// and its synonyms, recording the expansions in `applied`.
+    pub(super) fn expand(
+        &self,
+        node: QueryNode,
+        applied: &mut BTreeMap<String, Vec<String>>,
+    ) -> QueryNode {
+        let text = match &node {
+            QueryNode::Term {
+                text,
+                prefix: false,
+            } => text.clone(),
+            QueryNode::Phrase {
+                words,
+                prefix: false,
+            } => words.join(" "),
+            QueryNode::And(_) | QueryNode::Or(_) | QueryNode::Not(..) | QueryNode::Field { .. } => {
+                return self.expand_children(node, applied);
+            }
+            _ => return node,
+        };
+        let synonyms = self.synonyms_of(&text);
+        if synonyms.is_empty() {
+            return node;
+        }
+        applied.insert(
+            text,
+            synonyms.iter().map(|synonym| synonym.to_string()).collect(),
+        );
+        let mut alternatives = vec![node];
+        alternatives.extend(synonyms.into_iter().map(|synonym| {
+            let words: Vec<String> = synonym.split_whitespace().map(str::to_string).collect();
+            if words.len() == 1 {
+                QueryNode::Term {
+                    text: synonym.to_string(),
+                    prefix: false,
+                }
+            } else {
+                QueryNode::Phrase {
+                    words,
+                    prefix: false,
+                }
+            }
+        }));
+        QueryNode::Or(alternatives)
+    }
+
+    fn expand_children(
+        &self,
+        node: QueryNode,
+        applied: &mut BTreeMap<String, Vec<String>>,
+    ) -> QueryNode {
+        let mut expand_all = |nodes: Vec<QueryNode>| -> Vec<QueryNode> {
+            nodes
+                .into_iter()
+                .map(|node| self.expand(node, applied))
+                .collect()
+        };
+        match node {
+            QueryNode::And(nodes) => QueryNode::And(expand_all(nodes)),
+            QueryNode::Or(nodes) => QueryNode::Or(expand_all(nodes)),
+            // Excluded terms are left as written
+            QueryNode::Not(left, right) => {
+                QueryNode::Not(Box::new(self.expand(*left, applied)), right)
+            }
+            QueryNode::Field { name, node } => QueryNode::Field {
+                name,
+                node: Box::new(self.expand(*node, applied)),
+            },
+            node => node,
+        }
+    }
+}
+
+/// Lower-cased words of `term` joined by single spaces.
+fn normalize(term: &str) -> String {
+    term.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl std::fmt::Display for Synonyms {
+    /// Formats one comma-separated group per line, as read by
+    /// [`FromStr`](std::str::FromStr).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for group in &self.groups {
+            writeln!(f, "{}", group.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Synonyms {
+    type Err = LocalSearchError;
+
+    /// Parses one group of comma-separated terms per line, e.g. `db, database`.
+    /// Blank lines and lines starting with `#` are ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let groups = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split(',')
+                    .map(|term| term.trim().to_string())
+                    .collect()
+            })
+            .collect();
+        let synonyms = Synonyms { groups };
+        synonyms.validate()?;
+        Ok(synonyms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{QuerySyntax, parse_query};
+
+    #[test]
+    fn test_synonym_expansion() {
+        let synonyms: Synonyms = "# acronyms\nk8s, Kubernetes\n\nml, machine learning\n"
+            .parse()
+            .unwrap();
+        assert_eq!(synonyms.groups.len(), 2);
+        assert_eq!(synonyms.to_string().parse::<Synonyms>().unwrap(), synonyms);
+
+        let node = parse_query("K8S ml NOT db", QuerySyntax::Advanced, &[])
+            .unwrap()
+            .unwrap();
+        let mut applied = BTreeMap::new();
+        assert_eq!(
+            synonyms.expand(node, &mut applied).to_fts(),
+            "(\"K8S\" OR \"Kubernetes\") AND ((\"ml\" OR \"machine learning\") NOT \"db\")"
+        );
+        assert_eq!(applied["K8S"], vec!["Kubernetes"]);
+
+        let phrase = parse_query("\"machine learning\"", QuerySyntax::Advanced, &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            synonyms.expand(phrase, &mut applied).to_fts(),
+            "\"machine learning\" OR \"ml\""
+        );
+
+        assert!(matches!(
+            "k8s".parse::<Synonyms>(),
+            Err(LocalSearchError::InvalidArgument(_))
+        ));
+        assert!("k8s, ,kubernetes".parse::<Synonyms>().is_err());
+    }
+}
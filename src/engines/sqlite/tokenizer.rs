@@ -0,0 +1,211 @@
+//! Tokenizer of the full-text index.
+
+use crate::error::{LocalSearchError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Base FTS5 tokenizer, which decides how text is split into searchable tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerKind {
+    /// Splits on ASCII punctuation and folds ASCII case only; other characters are
+    /// kept as they are.
+    Ascii,
+    /// Splits on Unicode separators and folds case in all scripts.
+    Unicode61,
+    /// Indexes every 3-character sequence, so queries match substrings. Suits
+    /// languages written without spaces, such as Chinese or Japanese; query words
+    /// need at least 3 characters.
+    Trigram,
+}
+
+impl TokenizerKind {
+    /// Splits `text` into words roughly as the tokenizer does: ascii treats every
+    /// non-ASCII character as part of a word, the others keep letters and digits.
+    /// Trigram indexes are split into words as well rather than into trigrams.
+    pub(crate) fn words(self, text: &str) -> impl Iterator<Item = &str> {
+        text.split(move |c: char| {
+            !(c.is_alphanumeric() || self == TokenizerKind::Ascii && !c.is_ascii())
+        })
+        .filter(|word| !word.is_empty())
+    }
+}
+
+/// Tokenizer of a collection's full-text index, chosen when the index is created.
+/// The default, `porter ascii`, is what indexes used before it was configurable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tokenizer {
+    pub kind: TokenizerKind,
+    /// Apply English Porter stemming, so `running` also matches `runs`.
+    pub porter: bool,
+    /// Match letters regardless of accents, e.g. `munchen` finds `München`.
+    pub remove_diacritics: bool,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Tokenizer {
+            kind: TokenizerKind::Ascii,
+            porter: true,
+            remove_diacritics: false,
+        }
+    }
+}
+
+impl Tokenizer {
+    /// A tokenizer of the given kind without stemming or diacritic removal.
+    pub fn new(kind: TokenizerKind) -> Self {
+        Tokenizer {
+            kind,
+            porter: false,
+            remove_diacritics: false,
+        }
+    }
+
+    pub fn with_porter(mut self, porter: bool) -> Self {
+        self.porter = porter;
+        self
+    }
+
+    pub fn with_remove_diacritics(mut self, remove_diacritics: bool) -> Self {
+        self.remove_diacritics = remove_diacritics;
+        self
+    }
+
+    /// Rejects combinations FTS5 does not support or that make no sense.
+    pub fn validate(&self) -> Result<()> {
+        match self.kind {
+            TokenizerKind::Ascii if self.remove_diacritics => {
+                Err(LocalSearchError::InvalidArgument(
+                    "The ascii tokenizer cannot remove diacritics; use unicode61".to_string(),
+                ))
+            }
+            TokenizerKind::Trigram if self.porter => Err(LocalSearchError::InvalidArgument(
+                "Porter stemming cannot be combined with the trigram tokenizer".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The FTS5 `tokenize` option value.
+    pub(crate) fn to_sql(self) -> String {
+        let base = match self.kind {
+            TokenizerKind::Ascii => "ascii".to_string(),
+            // unicode61 removes diacritics by default; 2 also handles combined forms
+            TokenizerKind::Unicode61 => format!(
+                "unicode61 remove_diacritics {}",
+                if self.remove_diacritics { 2 } else { 0 }
+            ),
+            TokenizerKind::Trigram => format!(
+                "trigram remove_diacritics {}",
+                if self.remove_diacritics { 1 } else { 0 }
+            ),
+        };
+        if self.porter {
+            format!("porter {}", base)
+        } else {
+            base
+        }
+    }
+}
+
+impl std::fmt::Display for Tokenizer {
+    /// Formats the tokenizer in the form accepted by [`FromStr`](std::str::FromStr).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut words = Vec::new();
+        if self.porter {
+            words.push("porter");
+        }
+        words.push(match self.kind {
+            TokenizerKind::Ascii => "ascii",
+            TokenizerKind::Unicode61 => "unicode61",
+            TokenizerKind::Trigram => "trigram",
+        });
+        if self.remove_diacritics {
+            words.push("remove_diacritics");
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+impl std::str::FromStr for Tokenizer {
+    type Err = LocalSearchError;
+
+    /// Parses space-separated words: one of `ascii`, `unicode61` or `trigram`,
+    /// optionally with `porter` and `remove_diacritics`, e.g. `porter unicode61`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut kind = None;
+        let mut porter = false;
+        let mut remove_diacritics = false;
+        for word in s.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "porter" => porter = true,
+                "remove_diacritics" => remove_diacritics = true,
+                name => {
+                    let parsed = match name {
+                        "ascii" => TokenizerKind::Ascii,
+                        "unicode61" => TokenizerKind::Unicode61,
+                        "trigram" => TokenizerKind::Trigram,
+                        _ => {
+                            return Err(LocalSearchError::InvalidArgument(format!(
+                                "Unknown tokenizer option '{}'. Use 'ascii', 'unicode61' or 'trigram', optionally with 'porter' and 'remove_diacritics'.",
+                                word
+                            )));
+                        }
+                    };
+                    if kind.replace(parsed).is_some() {
+                        return Err(LocalSearchError::InvalidArgument(format!(
+                            "Tokenizer '{}' names more than one tokenizer",
+                            s
+                        )));
+                    }
+                }
+            }
+        }
+        let kind = kind.ok_or_else(|| {
+            LocalSearchError::InvalidArgument(format!(
+                "Tokenizer '{}' needs one of 'ascii', 'unicode61' or 'trigram'",
+                s
+            ))
+        })?;
+        let tokenizer = Tokenizer {
+            kind,
+            porter,
+            remove_diacritics,
+        };
+        tokenizer.validate()?;
+        Ok(tokenizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizer_parse_and_sql() {
+        let tokenizer: Tokenizer = "porter unicode61 remove_diacritics".parse().unwrap();
+        assert_eq!(tokenizer.to_sql(), "porter unicode61 remove_diacritics 2");
+        assert_eq!(tokenizer.to_string(), "porter unicode61 remove_diacritics");
+        assert_eq!(Tokenizer::default().to_sql(), "porter ascii");
+        assert_eq!(
+            "trigram".parse::<Tokenizer>().unwrap().to_sql(),
+            "trigram remove_diacritics 0"
+        );
+        for invalid in [
+            "",
+            "porter",
+            "ascii remove_diacritics",
+            "porter trigram",
+            "icu",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<Tokenizer>(),
+                    Err(LocalSearchError::InvalidArgument(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Error type returned by the engine, the embedder and the export format.
+
+/// Result type used across the public API.
+pub type Result<T> = std::result::Result<T, LocalSearchError>;
+
+/// Failure kinds callers can branch on.
+///
+/// Converts into `anyhow::Error`, from which it can be recovered with
+/// `error.downcast_ref::<LocalSearchError>()`.
+#[derive(Debug, thiserror::Error)]
+pub enum LocalSearchError {
+    /// The operation needs an embedder but none was configured.
+    #[error("{0} requires an embedder")]
+    MissingEmbedder(&'static str),
+    /// An embedding's length differs from the embeddings it is used with.
+    #[error("Embedding dimension mismatch: expected {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    /// Data was written with a newer, unsupported format or schema version.
+    #[error("Unsupported schema version {found}; this version supports up to {supported}")]
+    SchemaVersion { found: u32, supported: u32 },
+    /// A search query could not be parsed.
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    /// A named document, collection or file does not exist.
+    #[error("{0} not found")]
+    NotFound(String),
+    /// An argument was rejected, e.g. an invalid collection name.
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// Stored or imported data is malformed.
+    #[error("{0}")]
+    InvalidData(String),
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{context}: {source}")]
+    Sqlite {
+        context: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+    /// Loading the embedding model or computing an embedding failed.
+    #[error("{context}: {source}")]
+    Embedding {
+        context: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// A thread panicked while holding a lock or running a blocking task.
+    #[error("{0}")]
+    Panicked(String),
+}
+
+impl From<rusqlite::Error> for LocalSearchError {
+    fn from(source: rusqlite::Error) -> Self {
+        LocalSearchError::sqlite("Database error", source)
+    }
+}
+
+impl From<std::io::Error> for LocalSearchError {
+    fn from(source: std::io::Error) -> Self {
+        LocalSearchError::io("I/O error", source)
+    }
+}
+
+impl LocalSearchError {
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        LocalSearchError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn sqlite(context: impl Into<String>, source: rusqlite::Error) -> Self {
+        LocalSearchError::Sqlite {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn embedding(context: impl Into<String>, source: impl Into<anyhow::Error>) -> Self {
+        LocalSearchError::Embedding {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+}
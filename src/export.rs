@@ -0,0 +1,387 @@
+//! Portable export format for moving an index between machines.
+//!
+//! An export starts with an [`ExportHeader`] recording the embedding model, followed
+//! by one [`ExportedDocument`] per document. Two encodings are supported:
+//!
+//! - [`ExportFormat::Jsonl`]: the header and each document as one JSON object per line.
+//! - [`ExportFormat::Binary`]: a compact encoding that stores embeddings as raw
+//!   little-endian `f32`s; documents are length-prefixed JSON.
+//!
+//! [`ExportReader`] detects the encoding automatically.
+
+use crate::StoredDocument;
+use crate::error::{LocalSearchError, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Read, Write};
+
+/// Version of the export format written by this crate.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Magic bytes at the start of a binary export.
+const BINARY_MAGIC: &[u8; 4] = b"LSX\x01";
+
+/// Encoding of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[default]
+    Jsonl,
+    Binary,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = LocalSearchError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "jsonl" | "json" => Ok(ExportFormat::Jsonl),
+            "binary" | "bin" => Ok(ExportFormat::Binary),
+            _ => Err(LocalSearchError::InvalidArgument(format!(
+                "Unknown export format '{}'. Use 'jsonl' or 'binary'.",
+                s
+            ))),
+        }
+    }
+}
+
+/// Options for exporting an index.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Include stored embeddings so an import with the same model can skip embedding.
+    pub include_embeddings: bool,
+}
+
+/// First record of an export.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub format_version: u32,
+    /// Identifier of the model that produced the exported embeddings
+    /// (see [`LocalEmbedder::model_id`](crate::LocalEmbedder::model_id)).
+    pub embedding_model: Option<String>,
+    pub embedding_dimension: Option<usize>,
+    pub includes_embeddings: bool,
+}
+
+/// A document with its optional embedding, as stored in an export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedDocument {
+    #[serde(flatten)]
+    pub document: StoredDocument,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Result of importing an export.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub document_count: usize,
+    /// Documents whose exported embedding was stored as is.
+    pub reused_embeddings: usize,
+    /// Documents embedded during the import.
+    pub computed_embeddings: usize,
+}
+
+/// Writes an export document by document.
+pub struct ExportWriter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+}
+
+impl<W: Write> ExportWriter<W> {
+    /// Creates a writer and writes the header.
+    pub fn new(mut writer: W, format: ExportFormat, header: &ExportHeader) -> Result<Self> {
+        let header_json = serde_json::to_vec(header).map_err(encode_error)?;
+        match format {
+            ExportFormat::Jsonl => {
+                writer.write_all(&header_json)?;
+                writer.write_all(b"\n")?;
+            }
+            ExportFormat::Binary => {
+                writer.write_all(BINARY_MAGIC)?;
+                write_chunk(&mut writer, &header_json)?;
+            }
+        }
+        Ok(ExportWriter { writer, format })
+    }
+
+    /// Writes one document.
+    pub fn write(&mut self, document: &ExportedDocument) -> Result<()> {
+        match self.format {
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, document).map_err(encode_error)?;
+                self.writer.write_all(b"\n")?;
+            }
+            ExportFormat::Binary => {
+                let document_json = serde_json::to_vec(&document.document).map_err(encode_error)?;
+                write_chunk(&mut self.writer, &document_json)?;
+                let embedding = document.embedding.as_deref().unwrap_or_default();
+                self.writer
+                    .write_all(&(embedding.len() as u32).to_le_bytes())?;
+                for value in embedding {
+                    self.writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads an export, detecting its encoding from the first bytes.
+pub struct ExportReader<R: BufRead> {
+    reader: R,
+    format: ExportFormat,
+    header: ExportHeader,
+    line_number: usize,
+}
+
+impl<R: BufRead> ExportReader<R> {
+    /// Creates a reader and reads the header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let is_binary = reader.fill_buf()?.starts_with(BINARY_MAGIC);
+        let (format, header_bytes) = if is_binary {
+            reader.consume(BINARY_MAGIC.len());
+            let header = read_chunk(&mut reader)?.ok_or_else(|| {
+                LocalSearchError::InvalidData("Export is missing its header".to_string())
+            })?;
+            (ExportFormat::Binary, header)
+        } else {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            (ExportFormat::Jsonl, line.into_bytes())
+        };
+
+        let header: ExportHeader = serde_json::from_slice(&header_bytes)
+            .map_err(|e| LocalSearchError::InvalidData(format!("Invalid export header: {}", e)))?;
+        if header.format_version > FORMAT_VERSION {
+            return Err(LocalSearchError::SchemaVersion {
+                found: header.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        Ok(ExportReader {
+            reader,
+            format,
+            header,
+            line_number: 1,
+        })
+    }
+
+    pub fn header(&self) -> &ExportHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    fn read_document(&mut self) -> Result<Option<ExportedDocument>> {
+        match self.format {
+            ExportFormat::Jsonl => {
+                let mut line = String::new();
+                loop {
+                    line.clear();
+                    if self.reader.read_line(&mut line)? == 0 {
+                        return Ok(None);
+                    }
+                    self.line_number += 1;
+                    if !line.trim().is_empty() {
+                        break;
+                    }
+                }
+                serde_json::from_str(&line).map(Some).map_err(|e| {
+                    LocalSearchError::InvalidData(format!(
+                        "Invalid document on line {}: {}",
+                        self.line_number, e
+                    ))
+                })
+            }
+            ExportFormat::Binary => {
+                let Some(document_bytes) = read_chunk(&mut self.reader)? else {
+                    return Ok(None);
+                };
+                let document: StoredDocument =
+                    serde_json::from_slice(&document_bytes).map_err(|e| {
+                        LocalSearchError::InvalidData(format!("Invalid document in export: {}", e))
+                    })?;
+                let dimension = read_u32(&mut self.reader)? as usize;
+                let embedding = if dimension == 0 {
+                    None
+                } else {
+                    let length = dimension.checked_mul(4).ok_or_else(|| {
+                        LocalSearchError::InvalidData(format!(
+                            "Invalid embedding dimension {} in export",
+                            dimension
+                        ))
+                    })?;
+                    let bytes = read_bytes(&mut self.reader, length)?;
+                    Some(
+                        bytes
+                            .chunks_exact(4)
+                            .map(|chunk| {
+                                f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+                            })
+                            .collect(),
+                    )
+                };
+                Ok(Some(ExportedDocument {
+                    document,
+                    embedding,
+                }))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ExportReader<R> {
+    type Item = Result<ExportedDocument>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_document().transpose()
+    }
+}
+
+fn write_chunk(writer: &mut impl Write, bytes: &[u8]) -> Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|e| LocalSearchError::io("Truncated export", e))?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads a length-prefixed chunk, or `None` at the end of the input.
+fn read_chunk(reader: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let length = read_u32(reader)? as usize;
+    read_bytes(reader, length).map(Some)
+}
+
+/// Reads exactly `length` bytes. The buffer grows as data arrives, so a corrupt length
+/// fails as a truncated export instead of allocating it up front.
+fn read_bytes(reader: &mut impl Read, length: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .take(length as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| LocalSearchError::io("Failed to read export", e))?;
+    if bytes.len() != length {
+        return Err(LocalSearchError::io(
+            "Truncated export",
+            std::io::ErrorKind::UnexpectedEof.into(),
+        ));
+    }
+    Ok(bytes)
+}
+
+fn encode_error(error: serde_json::Error) -> LocalSearchError {
+    LocalSearchError::InvalidData(format!("Failed to encode export: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_documents() -> Vec<ExportedDocument> {
+        vec![
+            ExportedDocument {
+                document: StoredDocument {
+                    path: "a.md".to_string(),
+                    content: "first\nline".to_string(),
+                    metadata: Some([("k".to_string(), "v".to_string())].into()),
+                    created_at: 1.5,
+                    updated_at: 2.5,
+                },
+                embedding: Some(vec![0.1, -0.2, 0.3]),
+            },
+            ExportedDocument {
+                document: StoredDocument {
+                    path: "b.md".to_string(),
+                    content: "second".to_string(),
+                    metadata: None,
+                    created_at: 3.0,
+                    updated_at: 3.0,
+                },
+                embedding: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_both_formats() {
+        let header = ExportHeader {
+            format_version: FORMAT_VERSION,
+            embedding_model: Some("AllMiniLML6V2".to_string()),
+            embedding_dimension: Some(3),
+            includes_embeddings: true,
+        };
+        for format in [ExportFormat::Jsonl, ExportFormat::Binary] {
+            let mut writer = ExportWriter::new(Vec::new(), format, &header).unwrap();
+            for document in sample_documents() {
+                writer.write(&document).unwrap();
+            }
+            let bytes = writer.finish().unwrap();
+
+            let reader = ExportReader::new(bytes.as_slice()).unwrap();
+            assert_eq!(reader.format(), format);
+            assert_eq!(reader.header(), &header);
+            let documents: Vec<_> = reader.collect::<Result<_>>().unwrap();
+            assert_eq!(documents.len(), 2);
+            assert_eq!(documents[0].document.content, "first\nline");
+            assert_eq!(documents[0].document.updated_at, 2.5);
+            assert_eq!(documents[0].embedding, Some(vec![0.1, -0.2, 0.3]));
+            assert!(documents[1].embedding.is_none());
+        }
+    }
+
+    #[test]
+    fn test_truncated_binary_export_fails() {
+        let mut writer =
+            ExportWriter::new(Vec::new(), ExportFormat::Binary, &ExportHeader::default()).unwrap();
+        writer.write(&sample_documents()[0]).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let reader = ExportReader::new(&bytes[..bytes.len() - 2]).unwrap();
+        let results: Vec<_> = reader.collect();
+        assert!(results[0].is_err());
+
+        // A corrupt length prefix is not trusted for the allocation
+        let mut bytes =
+            ExportWriter::new(Vec::new(), ExportFormat::Binary, &ExportHeader::default())
+                .unwrap()
+                .finish()
+                .unwrap();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"{}");
+        let results: Vec<_> = ExportReader::new(bytes.as_slice()).unwrap().collect();
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn test_newer_format_version_is_rejected() {
+        let header = ExportHeader {
+            format_version: FORMAT_VERSION + 1,
+            ..ExportHeader::default()
+        };
+        let bytes = ExportWriter::new(Vec::new(), ExportFormat::Jsonl, &header)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert!(matches!(
+            ExportReader::new(bytes.as_slice()),
+            Err(LocalSearchError::SchemaVersion { found, .. }) if found == FORMAT_VERSION + 1
+        ));
+    }
+}
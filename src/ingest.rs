@@ -0,0 +1,541 @@
+//! Ingestion of files and streams into a [`DocumentIndexer`].
+//!
+//! An [`Ingestor`] walks a file or directory, picks an [`Extractor`] for each file by
+//! extension, applies the configured [`FileFilter`]s and runs the eligible files
+//! through the ingestion [`pipeline`]. Progress is reported to an [`IngestObserver`].
+//!
+//! ```rust,no_run
+//! use localsearch::SqliteLocalSearchEngine;
+//! use localsearch::ingest::{Ingestor, JsonExtractor, TextExtractor};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let engine = SqliteLocalSearchEngine::new("index.db", None)?;
+//! engine.create_table()?;
+//!
+//! let ingestor = Ingestor::new(Box::new(engine))
+//!     .with_extractor(JsonExtractor::new())
+//!     .with_extractor(TextExtractor::with_extensions(&["md", "txt"]))
+//!     .with_filter(|path: &std::path::Path| !path.to_string_lossy().contains("draft"));
+//! let result = ingestor.ingest("docs")?;
+//! println!("Indexed {} files", result.indexed_count);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod extract;
+pub mod pipeline;
+
+pub use extract::{
+    CsvColumnMapping, CsvExtractor, DocumentIter, Extractor, ExtractorRegistry, JsonExtractor,
+    TextExtractor,
+};
+pub use pipeline::PipelineConfig;
+
+use crate::{DocumentIndexer, DocumentRequest};
+use log::{debug, info};
+use pipeline::run_pipeline;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Callback used by extraction to hand documents to the ingestion pipeline.
+pub type Emit<'a> = &'a mut dyn FnMut(DocumentRequest) -> anyhow::Result<()>;
+
+/// Summary of an ingestion run.
+pub struct IngestionResult {
+    pub indexed_count: usize,
+    pub failed_count: usize,
+    pub failed_files: Vec<String>,
+    pub total_document_count: i64,
+    /// Number of documents written during this ingestion.
+    pub document_count: usize,
+    /// Wall-clock time spent ingesting.
+    pub elapsed: Duration,
+}
+
+impl IngestionResult {
+    pub fn new() -> Self {
+        IngestionResult {
+            indexed_count: 0,
+            failed_count: 0,
+            failed_files: Vec::new(),
+            total_document_count: 0,
+            document_count: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Documents written per second, or 0 if nothing was timed.
+    pub fn documents_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.document_count as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn add_success(&mut self) {
+        self.indexed_count += 1;
+    }
+
+    pub(crate) fn add_failure(&mut self, source: &str, error: &anyhow::Error) {
+        self.failed_count += 1;
+        self.failed_files
+            .push(source.to_string() + ": " + &error.to_string());
+        debug!("Failed to process {}: {}", source, error);
+    }
+}
+
+impl Default for IngestionResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receives progress events while an ingestor runs.
+///
+/// Ingestors never print; callers that want progress output (a progress bar, JSON
+/// events, logs) supply an observer. All methods default to doing nothing.
+pub trait IngestObserver {
+    /// Called once the eligible files are known, before any is processed.
+    fn on_started(&self, total_files: usize) {
+        let _ = total_files;
+    }
+
+    /// Called after all documents from a file were written.
+    fn on_file_processed(&self, path: &Path, documents: usize) {
+        let _ = (path, documents);
+    }
+
+    /// Called when a file (or a document read from a stream) could not be ingested.
+    fn on_file_failed(&self, path: &Path, error: &anyhow::Error) {
+        let _ = (path, error);
+    }
+
+    /// Called when ingestion is complete.
+    fn on_finished(&self, result: &IngestionResult) {
+        let _ = result;
+    }
+}
+
+/// Observer that ignores all events.
+pub struct NoopObserver;
+
+impl IngestObserver for NoopObserver {}
+
+/// Decides whether a file found while walking a directory should be ingested.
+pub trait FileFilter {
+    fn accept(&self, path: &Path) -> bool;
+}
+
+impl<F> FileFilter for F
+where
+    F: Fn(&Path) -> bool,
+{
+    fn accept(&self, path: &Path) -> bool {
+        self(path)
+    }
+}
+
+/// Ingests files and streams into a [`DocumentIndexer`] using pluggable extractors.
+///
+/// Only files with a registered [`Extractor`] that pass every [`FileFilter`] are
+/// ingested.
+pub struct Ingestor {
+    pub indexer: Box<dyn DocumentIndexer>,
+    pub extractors: ExtractorRegistry,
+    pub filters: Vec<Box<dyn FileFilter>>,
+    pub pipeline: PipelineConfig,
+    pub observer: Box<dyn IngestObserver>,
+}
+
+impl Ingestor {
+    /// Creates an ingestor with no extractors registered.
+    pub fn new(indexer: Box<dyn DocumentIndexer>) -> Self {
+        Ingestor {
+            indexer,
+            extractors: ExtractorRegistry::new(),
+            filters: Vec::new(),
+            pipeline: PipelineConfig::default(),
+            observer: Box::new(NoopObserver),
+        }
+    }
+
+    /// Registers an extractor for the file extensions and MIME types it declares.
+    pub fn with_extractor(mut self, extractor: impl Extractor + 'static) -> Self {
+        self.extractors.register(extractor);
+        self
+    }
+
+    /// Adds a filter that every ingested file must pass.
+    pub fn with_filter(mut self, filter: impl FileFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Sets the pipeline configuration used when ingesting files.
+    pub fn with_pipeline(mut self, pipeline: PipelineConfig) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Sets the observer notified about ingestion progress.
+    pub fn with_observer(mut self, observer: Box<dyn IngestObserver>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    fn should_process_file(&self, path: &Path) -> bool {
+        self.extractors.for_path(path).is_some() && self.filters.iter().all(|f| f.accept(path))
+    }
+
+    /// Ingests a file, or every eligible file directly inside a directory.
+    pub fn ingest(&self, path_str: &str) -> anyhow::Result<IngestionResult> {
+        let path = Path::new(path_str);
+        std::fs::metadata(path)
+            .map_err(|e| anyhow::anyhow!("Cannot access '{}': {}", path_str, e))?;
+        info!("Starting ingestion with path: {}", path_str);
+
+        let eligible_files: Vec<_> = if path.is_dir() {
+            std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|file_path| file_path.is_file() && self.should_process_file(file_path))
+                .collect()
+        } else if self.should_process_file(path) {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+        info!("Found {} files to process", eligible_files.len());
+        self.observer.on_started(eligible_files.len());
+
+        let extractors = &self.extractors;
+        let extract = |file_path: &Path, emit: Emit| -> anyhow::Result<()> {
+            let extractor = extractors
+                .for_path(file_path)
+                .ok_or_else(|| anyhow::anyhow!("No extractor for {:?}", file_path))?;
+            let reader = BufReader::new(std::fs::File::open(file_path)?);
+            for document in extractor.extract(Box::new(reader), &file_path.to_string_lossy())? {
+                emit(document?)?;
+            }
+            Ok(())
+        };
+
+        let mut result = run_pipeline(
+            self.indexer.as_ref(),
+            &eligible_files,
+            extract,
+            &self.pipeline,
+            self.observer.as_ref(),
+        );
+        info!(
+            "Completed processing {} files ({} succeeded, {} failed)",
+            result.indexed_count + result.failed_count,
+            result.indexed_count,
+            result.failed_count
+        );
+
+        self.update_total_document_count(&mut result);
+        self.observer.on_finished(&result);
+        Ok(result)
+    }
+
+    /// Ingests documents from a stream such as stdin using the extractor registered
+    /// for `key`, which may be a file extension (`json`, `txt`, `csv`) or a MIME type.
+    ///
+    /// `source` names the stream and becomes the document path for extractors that
+    /// produce a single document (e.g. [`TextExtractor`]). Each document counts as one
+    /// entry in the result, and a bad record is reported without stopping the stream.
+    pub fn ingest_reader(
+        &self,
+        reader: impl Read,
+        key: &str,
+        source: &str,
+    ) -> anyhow::Result<IngestionResult> {
+        let extractor = self
+            .extractors
+            .for_extension(key)
+            .or_else(|| self.extractors.for_mime_type(key))
+            .ok_or_else(|| anyhow::anyhow!("No extractor registered for '{}'", key))?;
+
+        let started = Instant::now();
+        let mut result = IngestionResult::new();
+        self.observer.on_started(1);
+
+        let documents = extractor.extract(Box::new(BufReader::new(reader)), source)?;
+        for document in documents {
+            let outcome = document.and_then(|d| Ok(self.indexer.upsert_document(d)?));
+            match outcome {
+                Ok(_) => {
+                    result.add_success();
+                    result.document_count += 1;
+                    self.observer.on_file_processed(Path::new(source), 1);
+                }
+                Err(e) => {
+                    result.add_failure(source, &e);
+                    self.observer.on_file_failed(Path::new(source), &e);
+                }
+            }
+        }
+
+        result.elapsed = started.elapsed();
+        self.update_total_document_count(&mut result);
+        self.observer.on_finished(&result);
+        Ok(result)
+    }
+
+    fn update_total_document_count(&self, ingestion_result: &mut IngestionResult) {
+        match self.indexer.stats() {
+            Ok(stats) => ingestion_result.total_document_count = stats.document_count,
+            Err(e) => {
+                debug!("Failed to retrieve document count: {}", e);
+                ingestion_result.total_document_count = -1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LocalSearch, SearchType, SqliteLocalSearchEngine};
+    use tempfile::TempDir;
+
+    fn create_ingestor(temp_dir: &TempDir) -> Ingestor {
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        Ingestor::new(Box::new(engine))
+    }
+
+    fn open_engine(temp_dir: &TempDir) -> SqliteLocalSearchEngine {
+        SqliteLocalSearchEngine::new(temp_dir.path().join("test.db").to_str().unwrap(), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_csv_ingestion_with_template_and_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let csv_path = temp_dir.path().join("tickets.csv");
+        std::fs::write(
+            &csv_path,
+            "id,title,body,priority\nT-1,Login broken,\"Users cannot sign in, 500 error\",high\nT-2,Slow search,Queries take seconds,low\n",
+        )
+        .unwrap();
+
+        let ingestor =
+            create_ingestor(&temp_dir).with_extractor(CsvExtractor::new(CsvColumnMapping {
+                delimiter: b',',
+                id_column: "id".to_string(),
+                content_columns: vec![],
+                content_template: Some("{title}: {body}".to_string()),
+                metadata_columns: vec!["priority".to_string()],
+            }));
+        let result = ingestor.ingest(csv_path.to_str().unwrap()).unwrap();
+        assert_eq!(result.indexed_count, 1);
+        assert_eq!(result.total_document_count, 2);
+
+        let results = open_engine(&temp_dir)
+            .search("sign", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "T-1");
+        let metadata = results[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.get("priority").map(String::as_str), Some("high"));
+    }
+
+    #[test]
+    fn test_json_reader_accepts_array_and_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let ingestor = create_ingestor(&temp_dir).with_extractor(JsonExtractor::new());
+
+        let array = r#"[{"path": "a", "content": "first", "metadata": null}]"#;
+        let result = ingestor
+            .ingest_reader(array.as_bytes(), "json", "<stdin>")
+            .unwrap();
+        assert_eq!(result.indexed_count, 1);
+
+        let lines = "{\"path\": \"b\", \"content\": \"second\", \"metadata\": null}\n\nnot json\n";
+        let result = ingestor
+            .ingest_reader(lines.as_bytes(), "application/x-ndjson", "<stdin>")
+            .unwrap();
+        assert_eq!(result.indexed_count, 1);
+        assert_eq!(result.failed_count, 1);
+        assert!(result.failed_files[0].starts_with("<stdin>: line 3"));
+        assert_eq!(result.total_document_count, 2);
+
+        assert!(
+            ingestor
+                .ingest_reader("".as_bytes(), "pdf", "<stdin>")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_filters_and_extractors_select_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs_dir = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs_dir).unwrap();
+        std::fs::write(docs_dir.join("notes.md"), "rust ownership notes").unwrap();
+        std::fs::write(docs_dir.join("draft.md"), "rust draft").unwrap();
+        std::fs::write(docs_dir.join("image.png"), "not text").unwrap();
+
+        let ingestor = create_ingestor(&temp_dir)
+            .with_extractor(TextExtractor::with_extensions(&["md"]))
+            .with_filter(|path: &Path| !path.to_string_lossy().contains("draft"));
+        let result = ingestor.ingest(docs_dir.to_str().unwrap()).unwrap();
+        assert_eq!(result.indexed_count, 1);
+        assert_eq!(result.failed_count, 0);
+
+        let results = open_engine(&temp_dir)
+            .search("rust", SearchType::FullText, Some(10), None)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].path.ends_with("notes.md"));
+    }
+
+    #[test]
+    fn test_json_files_go_to_the_json_extractor_in_any_order() {
+        let mut text_first = ExtractorRegistry::new();
+        text_first.register(TextExtractor::new());
+        text_first.register(JsonExtractor::new());
+        let mut json_first = ExtractorRegistry::new();
+        json_first.register(JsonExtractor::new());
+        json_first.register(TextExtractor::new());
+
+        for registry in [text_first, json_first] {
+            let extractor = registry.for_extension("JSON").unwrap();
+            assert!(
+                extractor
+                    .mime_types()
+                    .iter()
+                    .any(|m| m == "application/json")
+            );
+            assert!(registry.for_extension("md").is_some());
+            assert!(registry.for_mime_type("Text/Plain").is_some());
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl IngestObserver for RecordingObserver {
+        fn on_started(&self, total_files: usize) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("started {}", total_files));
+        }
+
+        fn on_file_processed(&self, path: &Path, documents: usize) {
+            let name = path.file_name().unwrap().to_string_lossy();
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("processed {} {}", name, documents));
+        }
+
+        fn on_file_failed(&self, path: &Path, _error: &anyhow::Error) {
+            let name = path.file_name().unwrap().to_string_lossy();
+            self.events.lock().unwrap().push(format!("failed {}", name));
+        }
+
+        fn on_finished(&self, result: &IngestionResult) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("finished {}", result.indexed_count));
+        }
+    }
+
+    #[test]
+    fn test_observer_receives_ingestion_events() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs_dir = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs_dir).unwrap();
+        std::fs::write(
+            docs_dir.join("good.json"),
+            r#"[{"path": "a", "content": "alpha"}, {"path": "b", "content": "beta"}]"#,
+        )
+        .unwrap();
+        std::fs::write(docs_dir.join("bad.json"), "not json").unwrap();
+
+        let observer = RecordingObserver::default();
+        let events = observer.events.clone();
+        let ingestor = create_ingestor(&temp_dir)
+            .with_extractor(JsonExtractor::new())
+            .with_observer(Box::new(observer));
+
+        let result = ingestor.ingest(docs_dir.to_str().unwrap()).unwrap();
+        assert_eq!(result.indexed_count, 1);
+        assert_eq!(result.failed_count, 1);
+
+        let mut events = events.lock().unwrap().clone();
+        assert_eq!(events.first().map(String::as_str), Some("started 2"));
+        assert_eq!(events.last().map(String::as_str), Some("finished 1"));
+        events.sort();
+        assert!(events.contains(&"processed good.json 2".to_string()));
+        assert!(events.contains(&"failed bad.json".to_string()));
+    }
+
+    #[test]
+    fn test_file_failing_partway_leaves_no_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs_dir = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs_dir).unwrap();
+        let mut lines: Vec<String> = (0..5)
+            .map(|i| format!(r#"{{"path": "bad/{}", "content": "line {}"}}"#, i, i))
+            .collect();
+        lines.push("not json".to_string());
+        std::fs::write(docs_dir.join("bad.jsonl"), lines.join("\n")).unwrap();
+        std::fs::write(
+            docs_dir.join("good.jsonl"),
+            r#"{"path": "good/a", "content": "alpha"}"#,
+        )
+        .unwrap();
+
+        let ingestor = create_ingestor(&temp_dir)
+            .with_extractor(JsonExtractor::new())
+            .with_pipeline(PipelineConfig {
+                workers: 1,
+                batch_size: 2,
+                queue_capacity: 1,
+            });
+        let result = ingestor.ingest(docs_dir.to_str().unwrap()).unwrap();
+        assert_eq!(result.indexed_count, 1);
+        assert_eq!(result.failed_count, 1);
+        assert!(result.failed_files[0].contains("bad.jsonl"));
+        assert_eq!(result.document_count, 1);
+        assert_eq!(result.total_document_count, 1);
+        assert!(
+            open_engine(&temp_dir)
+                .get_document("bad/0")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tsv_missing_column_fails_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let tsv_path = temp_dir.path().join("catalogue.tsv");
+        std::fs::write(&tsv_path, "sku\tname\nA1\tWidget\n").unwrap();
+
+        let ingestor =
+            create_ingestor(&temp_dir).with_extractor(CsvExtractor::new(CsvColumnMapping {
+                delimiter: b'\t',
+                id_column: "sku".to_string(),
+                content_columns: vec!["description".to_string()],
+                content_template: None,
+                metadata_columns: vec![],
+            }));
+        let result = ingestor.ingest(tsv_path.to_str().unwrap()).unwrap();
+        assert_eq!(result.indexed_count, 0);
+        assert_eq!(result.failed_count, 1);
+        assert!(result.failed_files[0].contains("description"));
+    }
+}
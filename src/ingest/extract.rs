@@ -0,0 +1,416 @@
+use crate::DocumentRequest;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Documents produced by an [`Extractor`], one `Result` per record so callers can
+/// decide whether a bad record aborts the whole source or is skipped.
+pub type DocumentIter<'a> = Box<dyn Iterator<Item = anyhow::Result<DocumentRequest>> + 'a>;
+
+/// Turns the bytes of a file (or stream) into documents.
+///
+/// Extractors are registered in an [`ExtractorRegistry`] under the file extensions and
+/// MIME types they handle. They run on the ingestion worker threads, hence `Send + Sync`.
+pub trait Extractor: Send + Sync {
+    /// Lowercase file extensions, without the leading dot, handled by this extractor.
+    fn extensions(&self) -> &[String];
+
+    /// Lowercase MIME types handled by this extractor.
+    fn mime_types(&self) -> &[String] {
+        &[]
+    }
+
+    /// Extracts documents from `reader`. `source` names the input (a file path or
+    /// `<stdin>`) and may be used as the document path.
+    fn extract<'a>(
+        &'a self,
+        reader: Box<dyn Read + 'a>,
+        source: &str,
+    ) -> anyhow::Result<DocumentIter<'a>>;
+}
+
+/// Set of extractors looked up by file extension or MIME type.
+///
+/// When several extractors claim the same key, the one registered last wins, so
+/// defaults can be overridden.
+#[derive(Clone, Default)]
+pub struct ExtractorRegistry {
+    extractors: Vec<Arc<dyn Extractor>>,
+}
+
+impl ExtractorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an extractor.
+    pub fn register(&mut self, extractor: impl Extractor + 'static) {
+        self.extractors.push(Arc::new(extractor));
+    }
+
+    /// Returns the extractor for a file extension (case-insensitive, without dot).
+    pub fn for_extension(&self, extension: &str) -> Option<&dyn Extractor> {
+        let extension = extension.to_lowercase();
+        self.extractors
+            .iter()
+            .rev()
+            .find(|e| e.extensions().contains(&extension))
+            .map(|e| e.as_ref())
+    }
+
+    /// Returns the extractor for a MIME type.
+    pub fn for_mime_type(&self, mime_type: &str) -> Option<&dyn Extractor> {
+        self.extractors
+            .iter()
+            .rev()
+            .find(|e| {
+                e.mime_types()
+                    .iter()
+                    .any(|m| m.eq_ignore_ascii_case(mime_type))
+            })
+            .map(|e| e.as_ref())
+    }
+
+    /// Returns the extractor for a file path based on its extension.
+    pub fn for_path(&self, path: &Path) -> Option<&dyn Extractor> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.for_extension(ext))
+    }
+
+    /// Returns true if no extractor is registered.
+    pub fn is_empty(&self) -> bool {
+        self.extractors.is_empty()
+    }
+}
+
+fn lowercase(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_lowercase()).collect()
+}
+
+/// Extracts [`DocumentRequest`]s from JSON: either an array of requests or JSON Lines
+/// with one request per line.
+pub struct JsonExtractor {
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+}
+
+impl JsonExtractor {
+    pub fn new() -> Self {
+        JsonExtractor {
+            extensions: lowercase(&["json", "jsonl", "ndjson"]),
+            mime_types: lowercase(&[
+                "application/json",
+                "application/x-ndjson",
+                "application/jsonl",
+            ]),
+        }
+    }
+}
+
+impl Default for JsonExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for JsonExtractor {
+    fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    fn extract<'a>(
+        &'a self,
+        reader: Box<dyn Read + 'a>,
+        _source: &str,
+    ) -> anyhow::Result<DocumentIter<'a>> {
+        let mut reader = BufReader::new(reader);
+        // Peek at the first non-whitespace byte to tell an array from JSON Lines
+        let is_array = loop {
+            let buffer = reader.fill_buf()?;
+            match buffer.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(index) => break buffer[index] == b'[',
+                None if buffer.is_empty() => break false,
+                None => {
+                    let consumed = buffer.len();
+                    reader.consume(consumed);
+                }
+            }
+        };
+
+        if is_array {
+            let doc_requests: Vec<DocumentRequest> = serde_json::from_reader(reader)?;
+            return Ok(Box::new(doc_requests.into_iter().map(Ok)));
+        }
+
+        let documents = reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|(line_number, line)| {
+                let line = line?;
+                serde_json::from_str::<DocumentRequest>(&line)
+                    .map_err(|e| anyhow::anyhow!("line {}: {}", line_number + 1, e))
+            });
+        Ok(Box::new(documents))
+    }
+}
+
+/// Extracts the whole input as one plain-text document whose path is the source.
+pub struct TextExtractor {
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+}
+
+impl TextExtractor {
+    /// Creates a text extractor for common text and source file extensions. JSON is
+    /// left to [`JsonExtractor`]; pass `json` to [`Self::with_extensions`] to index
+    /// JSON files as raw text.
+    pub fn new() -> Self {
+        Self::with_extensions(&[
+            "txt", "md", "py", "rs", "js", "ts", "html", "css", "xml", "yaml", "yml",
+        ])
+    }
+
+    /// Creates a text extractor for the given file extensions.
+    pub fn with_extensions(extensions: &[&str]) -> Self {
+        TextExtractor {
+            extensions: lowercase(extensions),
+            mime_types: lowercase(&["text/plain", "text/markdown"]),
+        }
+    }
+}
+
+impl Default for TextExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for TextExtractor {
+    fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    fn extract<'a>(
+        &'a self,
+        mut reader: Box<dyn Read + 'a>,
+        source: &str,
+    ) -> anyhow::Result<DocumentIter<'a>> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let document = DocumentRequest {
+            path: source.to_string(),
+            content,
+            metadata: None,
+            embedding: None,
+        };
+        Ok(Box::new(std::iter::once(Ok(document))))
+    }
+}
+
+/// Extracts one document per row of a CSV/TSV file according to a [`CsvColumnMapping`].
+pub struct CsvExtractor {
+    mapping: CsvColumnMapping,
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+}
+
+impl CsvExtractor {
+    /// Creates a CSV extractor; a tab delimiter registers it for TSV files instead.
+    pub fn new(mapping: CsvColumnMapping) -> Self {
+        let (extensions, mime_types) = if mapping.delimiter == b'\t' {
+            (
+                lowercase(&["tsv", "tab"]),
+                lowercase(&["text/tab-separated-values"]),
+            )
+        } else {
+            (lowercase(&["csv"]), lowercase(&["text/csv"]))
+        };
+        CsvExtractor {
+            mapping,
+            extensions,
+            mime_types,
+        }
+    }
+}
+
+impl Extractor for CsvExtractor {
+    fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    fn extract<'a>(
+        &'a self,
+        reader: Box<dyn Read + 'a>,
+        _source: &str,
+    ) -> anyhow::Result<DocumentIter<'a>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.mapping.delimiter)
+            .flexible(true)
+            .from_reader(reader);
+        let layout = self.mapping.layout(reader.headers()?)?;
+
+        // Rows are streamed rather than collected so large files stay cheap
+        let documents = reader.into_records().enumerate().map(move |(row, record)| {
+            let record = record?;
+            layout
+                .to_document(&record)
+                .map_err(|e| anyhow::anyhow!("Row {}: {}", row + 1, e))
+        });
+        Ok(Box::new(documents))
+    }
+}
+
+/// Column mapping that turns the rows of a CSV/TSV file into documents.
+#[derive(Debug, Clone)]
+pub struct CsvColumnMapping {
+    /// Field delimiter, `b','` for CSV and `b'\t'` for TSV.
+    pub delimiter: u8,
+    /// Column whose value becomes the document path.
+    pub id_column: String,
+    /// Columns concatenated (newline separated) into the document content.
+    pub content_columns: Vec<String>,
+    /// Optional template such as `"{title}\n{body}"` used instead of `content_columns`.
+    pub content_template: Option<String>,
+    /// Columns copied into the document metadata.
+    pub metadata_columns: Vec<String>,
+}
+
+/// A piece of a parsed content template: literal text or a column reference.
+enum TemplatePart {
+    Literal(String),
+    Column(usize),
+}
+
+/// Resolved column positions for the header of a single CSV/TSV file.
+struct CsvRowLayout {
+    id: usize,
+    content: Vec<TemplatePart>,
+    metadata: Vec<(String, usize)>,
+}
+
+impl CsvColumnMapping {
+    fn layout(&self, headers: &csv::StringRecord) -> anyhow::Result<CsvRowLayout> {
+        let column_index = |name: &str| -> anyhow::Result<usize> {
+            headers
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or_else(|| anyhow::anyhow!("Column '{}' not found in header", name))
+        };
+
+        let content = match &self.content_template {
+            Some(template) => Self::parse_template(template, &column_index)?,
+            None => {
+                if self.content_columns.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "At least one content column or a content template is required"
+                    ));
+                }
+                let mut parts = Vec::new();
+                for (i, name) in self.content_columns.iter().enumerate() {
+                    if i > 0 {
+                        parts.push(TemplatePart::Literal("\n".to_string()));
+                    }
+                    parts.push(TemplatePart::Column(column_index(name)?));
+                }
+                parts
+            }
+        };
+
+        let metadata = self
+            .metadata_columns
+            .iter()
+            .map(|name| Ok((name.clone(), column_index(name)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(CsvRowLayout {
+            id: column_index(&self.id_column)?,
+            content,
+            metadata,
+        })
+    }
+
+    /// Splits a template into literals and `{column}` references.
+    fn parse_template(
+        template: &str,
+        column_index: &dyn Fn(&str) -> anyhow::Result<usize>,
+    ) -> anyhow::Result<Vec<TemplatePart>> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|e| start + e)
+                .ok_or_else(|| anyhow::anyhow!("Unclosed '{{' in content template"))?;
+            if start > 0 {
+                parts.push(TemplatePart::Literal(rest[..start].to_string()));
+            }
+            parts.push(TemplatePart::Column(column_index(
+                rest[start + 1..end].trim(),
+            )?));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Literal(rest.to_string()));
+        }
+        // Allow literal escapes such as "\n" and "\t" typed on the command line
+        for part in parts.iter_mut() {
+            if let TemplatePart::Literal(text) = part {
+                *text = text.replace("\\n", "\n").replace("\\t", "\t");
+            }
+        }
+        Ok(parts)
+    }
+}
+
+impl CsvRowLayout {
+    fn to_document(&self, record: &csv::StringRecord) -> anyhow::Result<DocumentRequest> {
+        let field = |index: usize| record.get(index).unwrap_or("");
+
+        let path = field(self.id).trim();
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("Empty id column"));
+        }
+
+        let content = self
+            .content
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(text) => text.as_str(),
+                TemplatePart::Column(index) => field(*index),
+            })
+            .collect::<String>();
+
+        let metadata = if self.metadata.is_empty() {
+            None
+        } else {
+            Some(
+                self.metadata
+                    .iter()
+                    .map(|(name, index)| (name.clone(), field(*index).to_string()))
+                    .collect(),
+            )
+        };
+
+        Ok(DocumentRequest {
+            path: path.to_string(),
+            content,
+            metadata,
+            embedding: None,
+        })
+    }
+}
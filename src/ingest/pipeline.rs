@@ -0,0 +1,412 @@
+use crate::ingest::{Emit, IngestObserver, IngestionResult};
+use crate::{DocumentIndexer, DocumentRequest, LocalEmbedder};
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TryRecvError, sync_channel};
+use std::time::Instant;
+
+/// Tuning knobs for the ingestion pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Number of threads reading and extracting files.
+    pub workers: usize,
+    /// Maximum number of documents embedded (and written) together.
+    pub batch_size: usize,
+    /// Number of batches buffered between stages before producers block.
+    pub queue_capacity: usize,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        PipelineConfig {
+            workers: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            batch_size: 32,
+            queue_capacity: 8,
+        }
+    }
+}
+
+/// Message sent from the extraction workers to the embedding stage.
+enum Extracted {
+    Documents {
+        file: usize,
+        documents: Vec<DocumentRequest>,
+    },
+    Done {
+        file: usize,
+        error: Option<anyhow::Error>,
+    },
+}
+
+/// Message sent from the embedding stage to the writer.
+enum Embedded {
+    Documents {
+        file: usize,
+        documents: Vec<DocumentRequest>,
+        embeddings: anyhow::Result<Option<Vec<Vec<f32>>>>,
+    },
+    Done {
+        file: usize,
+        error: Option<anyhow::Error>,
+    },
+}
+
+/// Runs `extract` over `files` on a worker pool, embeds the extracted documents in
+/// batches on a dedicated thread and writes them from the calling thread, which is
+/// also where `observer` is notified as each file completes.
+///
+/// `extract` hands documents to the provided callback as it produces them, which lets
+/// large files stream through the pipeline. Bounded channels between the stages keep
+/// at most `queue_capacity` batches in flight, so memory stays bounded regardless of
+/// the input size.
+///
+/// A file that fails partway has the documents already written for it deleted again,
+/// so a failed file leaves none of its documents in the index.
+pub fn run_pipeline<E>(
+    indexer: &dyn DocumentIndexer,
+    files: &[PathBuf],
+    extract: E,
+    config: &PipelineConfig,
+    observer: &dyn IngestObserver,
+) -> IngestionResult
+where
+    E: Fn(&Path, Emit) -> anyhow::Result<()> + Sync,
+{
+    let started = Instant::now();
+    let batch_size = config.batch_size.max(1);
+    let workers = config.workers.clamp(1, files.len().max(1));
+    let embedder = indexer.embedder();
+    let next_file = AtomicUsize::new(0);
+    let mut result = IngestionResult::new();
+
+    std::thread::scope(|scope| {
+        let (extracted_tx, extracted_rx) = sync_channel::<Extracted>(config.queue_capacity);
+        let (embedded_tx, embedded_rx) = sync_channel::<Embedded>(config.queue_capacity);
+
+        for _ in 0..workers {
+            let extracted_tx = extracted_tx.clone();
+            let (next_file, extract) = (&next_file, &extract);
+            scope
+                .spawn(move || extract_worker(files, next_file, extract, batch_size, extracted_tx));
+        }
+        drop(extracted_tx);
+
+        scope.spawn(move || embed_stage(embedder, batch_size, extracted_rx, embedded_tx));
+
+        write_stage(indexer, files, embedded_rx, observer, &mut result);
+    });
+
+    result.elapsed = started.elapsed();
+    result
+}
+
+fn extract_worker<E>(
+    files: &[PathBuf],
+    next_file: &AtomicUsize,
+    extract: &E,
+    batch_size: usize,
+    tx: SyncSender<Extracted>,
+) where
+    E: Fn(&Path, Emit) -> anyhow::Result<()>,
+{
+    loop {
+        let file = next_file.fetch_add(1, Ordering::SeqCst);
+        let Some(file_path) = files.get(file) else {
+            return;
+        };
+
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut emit = |document: DocumentRequest| -> anyhow::Result<()> {
+            batch.push(document);
+            if batch.len() >= batch_size {
+                let documents = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                tx.send(Extracted::Documents { file, documents })
+                    .map_err(|_| anyhow::anyhow!("Ingestion pipeline stopped"))?;
+            }
+            Ok(())
+        };
+        let error = extract(file_path, &mut emit).err();
+
+        if !batch.is_empty()
+            && tx
+                .send(Extracted::Documents {
+                    file,
+                    documents: batch,
+                })
+                .is_err()
+        {
+            return;
+        }
+        if tx.send(Extracted::Done { file, error }).is_err() {
+            return;
+        }
+    }
+}
+
+fn embed_stage(
+    embedder: Option<&LocalEmbedder>,
+    batch_size: usize,
+    rx: Receiver<Extracted>,
+    tx: SyncSender<Embedded>,
+) {
+    // Messages are held back until enough documents have accumulated for a batch, or
+    // no more input is immediately available. Order is preserved so a file's `Done`
+    // marker always reaches the writer after its documents.
+    let mut pending: Vec<Extracted> = Vec::new();
+    let mut pending_documents = 0;
+
+    while let Ok(message) = rx.recv() {
+        let mut message = Some(message);
+        loop {
+            if let Some(message) = message.take() {
+                if let Extracted::Documents { documents, .. } = &message {
+                    pending_documents += documents.len();
+                }
+                pending.push(message);
+            }
+            if pending_documents >= batch_size {
+                break;
+            }
+            match rx.try_recv() {
+                Ok(next) => message = Some(next),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        if flush_embeddings(embedder, &mut pending, &tx).is_err() {
+            return;
+        }
+        pending_documents = 0;
+    }
+}
+
+fn flush_embeddings(
+    embedder: Option<&LocalEmbedder>,
+    pending: &mut Vec<Extracted>,
+    tx: &SyncSender<Embedded>,
+) -> Result<(), ()> {
+    // Documents carrying a precomputed embedding are not embedded again
+    let texts: Vec<&str> = pending
+        .iter()
+        .flat_map(|message| match message {
+            Extracted::Documents { documents, .. } => documents.as_slice(),
+            Extracted::Done { .. } => &[],
+        })
+        .filter(|document| document.embedding.is_none())
+        .map(|document| document.content.as_str())
+        .collect();
+    let mut combined = match embedder {
+        Some(embedder) if !texts.is_empty() => {
+            debug!("Embedding batch of {} documents", texts.len());
+            match embedder.embed_batch(texts) {
+                Ok(embeddings) => Some(embeddings.into_iter()),
+                Err(e) => {
+                    // Embed each message on its own so only the failing file fails
+                    debug!("Embedding batch failed, retrying per file: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    for message in pending.drain(..) {
+        let embedded = match message {
+            Extracted::Documents {
+                file,
+                mut documents,
+            } => {
+                let embeddings = match (&mut combined, embedder) {
+                    (Some(computed), _) => Ok(pair_embeddings(&mut documents, computed)),
+                    (None, Some(embedder))
+                        if documents
+                            .iter()
+                            .any(|document| document.embedding.is_none()) =>
+                    {
+                        let texts = documents
+                            .iter()
+                            .filter(|document| document.embedding.is_none())
+                            .map(|document| document.content.as_str())
+                            .collect();
+                        embedder
+                            .embed_batch(texts)
+                            .map(|computed| {
+                                pair_embeddings(&mut documents, &mut computed.into_iter())
+                            })
+                            .map_err(|e| anyhow::anyhow!("Failed to embed batch: {}", e))
+                    }
+                    (None, _) => Ok(None),
+                };
+                Embedded::Documents {
+                    file,
+                    documents,
+                    embeddings,
+                }
+            }
+            Extracted::Done { file, error } => Embedded::Done { file, error },
+        };
+        tx.send(embedded).map_err(|_| ())?;
+    }
+    Ok(())
+}
+
+/// Pairs each document with its precomputed embedding, or else the next computed one.
+fn pair_embeddings(
+    documents: &mut [DocumentRequest],
+    computed: &mut impl Iterator<Item = Vec<f32>>,
+) -> Option<Vec<Vec<f32>>> {
+    documents
+        .iter_mut()
+        .map(|document| document.embedding.take().or_else(|| computed.next()))
+        .collect()
+}
+
+fn write_stage(
+    indexer: &dyn DocumentIndexer,
+    files: &[PathBuf],
+    rx: Receiver<Embedded>,
+    observer: &dyn IngestObserver,
+    result: &mut IngestionResult,
+) {
+    let mut file_errors: Vec<Option<anyhow::Error>> = files.iter().map(|_| None).collect();
+    // Paths written for each file so far, deleted again if the file fails
+    let mut file_paths: Vec<Vec<String>> = files.iter().map(|_| Vec::new()).collect();
+
+    for message in rx {
+        match message {
+            Embedded::Documents {
+                file,
+                documents,
+                embeddings,
+            } => {
+                if file_errors[file].is_some() {
+                    continue;
+                }
+                let paths: Vec<String> = documents.iter().map(|d| d.path.clone()).collect();
+                let written = embeddings
+                    .and_then(|embeddings| Ok(indexer.upsert_batch(documents, embeddings)?));
+                match written {
+                    Ok(_) => file_paths[file].extend(paths),
+                    Err(e) => file_errors[file] = Some(e),
+                }
+            }
+            Embedded::Done { file, error } => {
+                let file_path = &files[file];
+                let paths = std::mem::take(&mut file_paths[file]);
+                match error.or_else(|| file_errors[file].take()) {
+                    None => {
+                        result.add_success();
+                        result.document_count += paths.len();
+                        debug!("✓ Successfully indexed: {:?}", file_path);
+                        observer.on_file_processed(file_path, paths.len());
+                    }
+                    Some(e) => {
+                        for path in &paths {
+                            if let Err(delete_error) = indexer.delete_document(path) {
+                                warn!(
+                                    "Failed to remove {} of failed file {:?}: {}",
+                                    path, file_path, delete_error
+                                );
+                            }
+                        }
+                        result.add_failure(&file_path.to_string_lossy(), &e);
+                        observer.on_file_failed(file_path, &e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::NoopObserver;
+    use crate::{LocalSearch, SearchOptions, SqliteLocalSearchEngine};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pipeline_processes_all_files_with_bounded_queues() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+
+        let files: Vec<PathBuf> = (0..20)
+            .map(|i| temp_dir.path().join(format!("file{}.txt", i)))
+            .collect();
+        for (i, file) in files.iter().enumerate() {
+            // Every file yields several documents so batches span file boundaries
+            std::fs::write(file, format!("alpha beta {}", i)).unwrap();
+        }
+        let broken = temp_dir.path().join("missing.txt");
+        let mut all_files = files.clone();
+        all_files.push(broken.clone());
+
+        let extract = |path: &Path, emit: Emit| -> anyhow::Result<()> {
+            let content = std::fs::read_to_string(path)?;
+            for part in 0..3 {
+                emit(DocumentRequest {
+                    path: format!("{}#{}", path.display(), part),
+                    content: content.clone(),
+                    metadata: None,
+                    embedding: None,
+                })?;
+            }
+            Ok(())
+        };
+        let config = PipelineConfig {
+            workers: 4,
+            batch_size: 2,
+            queue_capacity: 1,
+        };
+
+        let result = run_pipeline(&engine, &all_files, extract, &config, &NoopObserver);
+        assert_eq!(result.indexed_count, 20);
+        assert_eq!(result.failed_count, 1);
+        assert!(result.failed_files[0].contains("missing.txt"));
+        assert_eq!(result.document_count, 60);
+        assert_eq!(engine.stats().unwrap().document_count, 60);
+    }
+
+    #[test]
+    fn test_pipeline_keeps_precomputed_embeddings() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        let file = temp_dir.path().join("vectors.json");
+        std::fs::write(&file, "").unwrap();
+
+        let extract = |_: &Path, emit: Emit| -> anyhow::Result<()> {
+            for (path, embedding) in [("a", vec![3.0, 4.0, 0.0]), ("b", vec![0.0, 0.0, 2.0])] {
+                emit(DocumentRequest {
+                    path: path.to_string(),
+                    content: format!("document {}", path),
+                    metadata: None,
+                    embedding: Some(embedding),
+                })?;
+            }
+            Ok(())
+        };
+        let result = run_pipeline(
+            &engine,
+            &[file],
+            extract,
+            &PipelineConfig::default(),
+            &NoopObserver,
+        );
+        assert_eq!(result.document_count, 2);
+
+        let options = SearchOptions::default();
+        let results = engine.search_by_vector(&[3.0, 4.0, 0.0], &options).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "a");
+        let results = engine.search_by_vector(&[0.0, 0.0, 1.0], &options).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "b");
+    }
+}
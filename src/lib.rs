@@ -6,8 +6,27 @@
 //!
 //! - Semantic search using vector embeddings
 //! - Local file indexing and search
+//! - Extensible ingestion of files and streams (JSON, text, CSV/TSV)
+//! - Portable export and import of indexes, optionally with embeddings
+//! - Online backups and read-only snapshots
+//! - Safe handling of user queries, with an advanced mode for boolean, phrase, prefix
+//!   and `NEAR` operators
+//! - Field-scoped full-text search over content, paths and metadata, with per-field
+//!   ranking weights
+//! - Configurable full-text tokenizers for non-English text and substring search
+//! - Typo-tolerant full-text matching with "did you mean" suggestions
+//! - Synonym groups and pseudo-relevance feedback expansion of full-text queries
+//! - Score explanations from raw BM25 and cosine similarity to the blended score
+//! - More-like-this search from stored embeddings and distinctive terms
+//! - Vector search and document embeddings computed outside the engine
+//! - Maximal marginal relevance re-ranking to avoid near-duplicate results
+//! - Named collections in one database, searchable individually or together
+//! - Thread-safe shared engine with concurrent readers and a single writer
 //! - SQLite-based storage
 //! - Both library and CLI interfaces
+//! - Local HTTP server keeping the model loaded between queries (`server` feature)
+//! - Model Context Protocol server for AI assistants (`mcp` feature)
+//! - Async API running on tokio's blocking pool, with result streams (`async` feature)
 //! - Configurable cache and database directories using system directories
 //! - Support for custom local ONNX models and tokenizers
 //!
@@ -40,6 +59,7 @@
 //!     path: "some/unique/path".to_string(),
 //!     content: "This is example content".to_string(),
 //!     metadata: None,
+//!     embedding: None,
 //! })?;
 //!
 //! // Search
@@ -52,8 +72,15 @@
 //! # }
 //! ```
 
+pub mod error;
+pub use error::{LocalSearchError, Result};
+
 pub mod traits;
-pub use traits::{DocumentIndexer, DocumentRequest, LocalSearch, SearchResult, SearchType};
+pub use traits::{
+    DocumentIndexer, DocumentInfo, DocumentRequest, IndexStats, ListOptions, LocalSearch,
+    QueryExpansion, QueryExplanation, ScoreExplanation, SearchOptions, SearchOutcome, SearchResult,
+    SearchType, StoredDocument,
+};
 
 pub mod config;
 pub use config::LocalSearchDirs;
@@ -62,4 +89,24 @@ pub mod embed;
 pub use embed::LocalEmbedder;
 
 pub mod engines;
-pub use engines::SqliteLocalSearchEngine;
+pub use engines::{
+    CollectionInfo, DEFAULT_COLLECTION, MetadataField, SearchFields, SharedSqliteEngine,
+    SqliteLocalSearchEngine, Synonyms, Tokenizer, TokenizerKind,
+};
+
+pub mod export;
+
+pub mod query;
+pub use query::QuerySyntax;
+
+pub mod ingest;
+pub use ingest::{IngestionResult, Ingestor};
+
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "mcp")]
+pub mod mcp;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
@@ -0,0 +1,492 @@
+//! [Model Context Protocol](https://modelcontextprotocol.io) server over stdio.
+//!
+//! Exposes an index to AI assistants as MCP tools. Messages are JSON-RPC 2.0, one
+//! per line, read from stdin and written to stdout; logs must go to stderr.
+//!
+//! Tools:
+//! - `search`: full-text, semantic or hybrid search with path and metadata filters
+//! - `get_document`: the stored content and metadata of a document by path
+//! - `find_similar`: documents similar to an indexed one, from its stored embedding
+//!   and distinctive terms
+//! - `index_stats`: statistics about the index
+
+use crate::{DocumentIndexer, LocalSearch, QueryExpansion, QuerySyntax, SearchOptions, SearchType};
+use anyhow::anyhow;
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// MCP protocol version implemented by this server.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Arguments of the `search` tool.
+#[derive(Debug, Deserialize)]
+struct SearchArguments {
+    query: String,
+    #[serde(default)]
+    search_type: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    path_filters: Option<Vec<String>>,
+    #[serde(default)]
+    metadata_filters: Option<HashMap<String, String>>,
+    #[serde(default)]
+    collections: Option<Vec<String>>,
+    #[serde(default)]
+    query_syntax: QuerySyntax,
+    #[serde(default)]
+    fuzzy: bool,
+    #[serde(default)]
+    expand: bool,
+    #[serde(default)]
+    explain: bool,
+    #[serde(default)]
+    mmr_lambda: Option<f64>,
+}
+
+/// Arguments of the `find_similar` tool.
+#[derive(Debug, Deserialize)]
+struct FindSimilarArguments {
+    path: String,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    terms: usize,
+    #[serde(default)]
+    path_filters: Option<Vec<String>>,
+    #[serde(default)]
+    metadata_filters: Option<HashMap<String, String>>,
+    #[serde(default)]
+    collections: Option<Vec<String>>,
+}
+
+/// Arguments of the `get_document` tool.
+#[derive(Debug, Deserialize)]
+struct GetDocumentArguments {
+    path: String,
+}
+
+/// JSON-RPC error with a code, returned to the client.
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// MCP server answering tool calls from a single engine.
+pub struct McpServer<E> {
+    engine: E,
+}
+
+impl<E: DocumentIndexer + LocalSearch> McpServer<E> {
+    pub fn new(engine: E) -> Self {
+        McpServer { engine }
+    }
+
+    /// Serves messages from `reader` until it is closed, writing responses to `writer`.
+    pub fn run(&self, reader: impl BufRead, mut writer: impl Write) -> anyhow::Result<()> {
+        for line in reader.lines() {
+            let line = line.map_err(|e| anyhow!("Failed to read message: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line) {
+                writeln!(writer, "{}", response)
+                    .and_then(|_| writer.flush())
+                    .map_err(|e| anyhow!("Failed to write response: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one JSON-RPC message and returns the response, or `None` for
+    /// notifications.
+    pub fn handle_message(&self, message: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+                ));
+            }
+        };
+
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "Missing method"),
+            ));
+        };
+        debug!("MCP request: {}", method);
+
+        // Notifications have no id and get no response
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let response = match self.dispatch(method, params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        };
+        Some(response)
+    }
+
+    fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": "localsearch",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => {
+                let name = params
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+                // Tool failures are reported in the result so the model can see them
+                let result = match self.call_tool(name, arguments) {
+                    Ok(text) => json!({
+                        "content": [{ "type": "text", "text": text }],
+                        "isError": false,
+                    }),
+                    Err(e) => {
+                        warn!("Tool {} failed: {}", name, e);
+                        json!({
+                            "content": [{ "type": "text", "text": e.to_string() }],
+                            "isError": true,
+                        })
+                    }
+                };
+                Ok(result)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    fn call_tool(&self, name: &str, arguments: Value) -> anyhow::Result<String> {
+        match name {
+            "search" => {
+                let arguments: SearchArguments = serde_json::from_value(arguments)
+                    .map_err(|e| anyhow!("Invalid arguments for search: {}", e))?;
+                let search_type: SearchType = arguments
+                    .search_type
+                    .as_deref()
+                    .unwrap_or("hybrid")
+                    .parse()?;
+                let options = SearchOptions {
+                    limit: arguments.limit,
+                    path_filters: arguments.path_filters,
+                    metadata_filters: arguments.metadata_filters,
+                    collections: arguments.collections,
+                    query_syntax: arguments.query_syntax,
+                    fuzzy: arguments.fuzzy,
+                    query_expansion: arguments.expand.then(QueryExpansion::default),
+                    explain: arguments.explain,
+                    mmr_lambda: arguments.mmr_lambda,
+                };
+                let results =
+                    self.engine
+                        .search_with_options(&arguments.query, search_type, &options)?;
+                Ok(serde_json::to_string_pretty(&results)?)
+            }
+            "find_similar" => {
+                let arguments: FindSimilarArguments = serde_json::from_value(arguments)
+                    .map_err(|e| anyhow!("Invalid arguments for find_similar: {}", e))?;
+                let options = SearchOptions {
+                    limit: arguments.limit,
+                    path_filters: arguments.path_filters,
+                    metadata_filters: arguments.metadata_filters,
+                    collections: arguments.collections,
+                    ..SearchOptions::default()
+                };
+                let results =
+                    self.engine
+                        .find_similar(&arguments.path, arguments.terms, &options)?;
+                Ok(serde_json::to_string_pretty(&results)?)
+            }
+            "get_document" => {
+                let arguments: GetDocumentArguments = serde_json::from_value(arguments)
+                    .map_err(|e| anyhow!("Invalid arguments for get_document: {}", e))?;
+                let document = self
+                    .engine
+                    .get_document(&arguments.path)?
+                    .ok_or_else(|| anyhow!("Document not found: {}", arguments.path))?;
+                Ok(serde_json::to_string_pretty(&document)?)
+            }
+            "index_stats" => {
+                let stats = self.engine.stats()?;
+                Ok(serde_json::to_string_pretty(&stats)?)
+            }
+            _ => Err(anyhow!("Unknown tool: {}", name)),
+        }
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search",
+            "description": "Search the indexed documents. Returns matching document paths with scores and metadata.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search query" },
+                    "search_type": {
+                        "type": "string",
+                        "enum": ["fulltext", "semantic", "hybrid"],
+                        "description": "'fulltext' for keyword search, 'semantic' for embedding similarity, 'hybrid' (default) for both"
+                    },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of results (default 10)" },
+                    "path_filters": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only return documents whose path contains any of these substrings"
+                    },
+                    "metadata_filters": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Only return documents whose metadata has all of these key/value pairs"
+                    },
+                    "collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Collections to search; [\"*\"] searches all collections"
+                    },
+                    "query_syntax": {
+                        "type": "string",
+                        "enum": ["plain", "advanced"],
+                        "description": "plain (default) matches the words literally; advanced allows AND, OR, NOT, parentheses, \"phrases\", prefix* and NEAR(a b, 5)"
+                    },
+                    "fuzzy": {
+                        "type": "boolean",
+                        "description": "Also match words within a few typos of query words that do not occur in the index"
+                    },
+                    "expand": {
+                        "type": "boolean",
+                        "description": "Add distinctive words of the closest documents by meaning to the full-text query"
+                    },
+                    "explain": {
+                        "type": "boolean",
+                        "description": "Include how each result's scores were computed: raw BM25, cosine similarity, fusion weights and ranks"
+                    },
+                    "mmr_lambda": {
+                        "type": "number",
+                        "minimum": 0,
+                        "maximum": 1,
+                        "description": "Diversify results: 1 ranks by relevance alone, lower values skip near-duplicates of higher-ranked results (0.7 is a good start)"
+                    }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "find_similar",
+            "description": "Find documents similar to an indexed document, by its stored embedding and optionally its most distinctive words. The document itself is excluded.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path of the document to find similar documents for" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of results (default 10)" },
+                    "terms": { "type": "integer", "minimum": 0, "description": "Number of the document's most distinctive words to also match by full-text search (default 0: embedding only)" },
+                    "path_filters": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only return documents whose path contains any of these substrings"
+                    },
+                    "metadata_filters": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Only return documents whose metadata has all of these key/value pairs"
+                    },
+                    "collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Collections to search; [\"*\"] searches all collections"
+                    }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "get_document",
+            "description": "Get the full stored content, metadata and timestamps of a document by its path.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Document path as returned by search" }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "index_stats",
+            "description": "Get statistics about the index: document and embedding counts, embedding model, content size, last update time and full-text index health.",
+            "inputSchema": { "type": "object", "properties": {} }
+        }
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DocumentRequest, SqliteLocalSearchEngine};
+    use tempfile::TempDir;
+
+    fn create_server(temp_dir: &TempDir) -> McpServer<SqliteLocalSearchEngine> {
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        for (path, content, kind) in [
+            ("notes/rust.md", "Rust borrow checker notes", "note"),
+            ("tickets/42.md", "Rust compile error in CI", "ticket"),
+        ] {
+            engine
+                .insert_document(DocumentRequest {
+                    path: path.to_string(),
+                    content: content.to_string(),
+                    metadata: Some(HashMap::from([("kind".to_string(), kind.to_string())])),
+                    embedding: None,
+                })
+                .unwrap();
+        }
+        McpServer::new(engine)
+    }
+
+    fn call_tool(
+        server: &McpServer<SqliteLocalSearchEngine>,
+        name: &str,
+        arguments: Value,
+    ) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        });
+        server.handle_message(&request.to_string()).unwrap()["result"].clone()
+    }
+
+    #[test]
+    fn test_stdio_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = create_server(&temp_dir);
+        let input = [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}}}"#,
+            r#"{"jsonrpc": "2.0", "method": "notifications/initialized"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/list"}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "resources/list"}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        server.run(input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        // The notification gets no response
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["result"]["protocolVersion"], PROTOCOL_VERSION);
+        let tools: Vec<_> = responses[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            tools,
+            vec!["search", "find_similar", "get_document", "index_stats"]
+        );
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[3]["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_search_tool_with_filters() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = create_server(&temp_dir);
+
+        let result = call_tool(
+            &server,
+            "search",
+            json!({ "query": "rust", "search_type": "fulltext", "metadata_filters": { "kind": "ticket" } }),
+        );
+        assert_eq!(result["isError"], false);
+        let results: Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["path"], "tickets/42.md");
+
+        let result = call_tool(
+            &server,
+            "search",
+            json!({ "query": "rust", "search_type": "fts", "path_filters": ["notes"] }),
+        );
+        let results: Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(results[0]["path"], "notes/rust.md");
+
+        // Semantic search needs an embedder; the failure is reported as a tool error
+        let result = call_tool(
+            &server,
+            "search",
+            json!({ "query": "rust", "search_type": "semantic" }),
+        );
+        assert_eq!(result["isError"], true);
+    }
+
+    #[test]
+    fn test_get_document_and_stats_tools() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = create_server(&temp_dir);
+
+        let result = call_tool(&server, "get_document", json!({ "path": "notes/rust.md" }));
+        let document: Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(document["content"], "Rust borrow checker notes");
+
+        let result = call_tool(&server, "get_document", json!({ "path": "missing.md" }));
+        assert_eq!(result["isError"], true);
+
+        let result = call_tool(&server, "index_stats", json!({}));
+        let stats: Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(stats["document_count"], 2);
+    }
+}
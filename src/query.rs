@@ -0,0 +1,609 @@
+//! Parsing of user search queries into safe FTS5 match expressions.
+//!
+//! In [`QuerySyntax::Plain`] mode every word is matched literally, so input like
+//! `C++`, `foo-bar` or `AND` can never produce an FTS5 syntax error. In
+//! [`QuerySyntax::Advanced`] mode the query may use these operators, which are
+//! validated before reaching SQLite:
+//!
+//! - `AND`, `OR`, `NOT` (upper case) and parentheses for grouping; adjacent terms
+//!   are combined with `AND`
+//! - `"exact phrase"`
+//! - `prefix*` for words (or phrases) starting with `prefix`
+//! - `NEAR(term term ..., distance)` for terms within `distance` tokens (default 10)
+//! - `field:term`, `field:"phrase"` or `field:(expression)` to match in one field only
+//!
+//! Field scoping also works in plain mode, for the field names passed to
+//! [`parse_query`]; other words containing `:` are matched literally there.
+
+use crate::error::{LocalSearchError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Default token distance of a `NEAR` group, as in FTS5.
+const DEFAULT_NEAR_DISTANCE: usize = 10;
+
+/// How a search query is interpreted by full-text search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuerySyntax {
+    /// Words are matched literally and all must occur; operators have no meaning.
+    #[default]
+    Plain,
+    /// Boolean operators, phrases, prefixes and `NEAR` groups, validated on parse.
+    Advanced,
+}
+
+impl std::str::FromStr for QuerySyntax {
+    type Err = LocalSearchError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(QuerySyntax::Plain),
+            "advanced" => Ok(QuerySyntax::Advanced),
+            _ => Err(LocalSearchError::InvalidArgument(format!(
+                "Unknown query syntax '{}'. Use 'plain' or 'advanced'.",
+                s
+            ))),
+        }
+    }
+}
+
+/// A parsed query expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    /// A single word, optionally matching as a prefix.
+    Term {
+        text: String,
+        prefix: bool,
+    },
+    /// Words that must occur next to each other in order.
+    Phrase {
+        words: Vec<String>,
+        prefix: bool,
+    },
+    /// Terms or phrases within `distance` tokens of each other.
+    Near {
+        items: Vec<QueryNode>,
+        distance: usize,
+    },
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    /// Matches the left side unless the right side also matches.
+    Not(Box<QueryNode>, Box<QueryNode>),
+    /// An expression matched in one indexed field only.
+    Field {
+        name: String,
+        node: Box<QueryNode>,
+    },
+}
+
+impl QueryNode {
+    /// Renders the expression as an FTS5 match expression with every term quoted.
+    pub fn to_fts(&self) -> String {
+        match self {
+            QueryNode::Term { text, prefix } => quote(text, *prefix),
+            QueryNode::Phrase { words, prefix } => quote(&words.join(" "), *prefix),
+            QueryNode::Near { items, distance } => format!(
+                "NEAR({}, {})",
+                items
+                    .iter()
+                    .map(QueryNode::to_fts)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                distance
+            ),
+            QueryNode::And(nodes) => join_group(nodes, " AND "),
+            QueryNode::Or(nodes) => join_group(nodes, " OR "),
+            QueryNode::Not(left, right) => {
+                format!("{} NOT {}", group(left), group(right))
+            }
+            QueryNode::Field { name, node } => format!("{} : {}", name, group(node)),
+        }
+    }
+
+    /// Returns the words of all terms and phrases, e.g. for highlighting or suggestions.
+    pub fn words(&self) -> Vec<&str> {
+        match self {
+            QueryNode::Term { text, .. } => vec![text.as_str()],
+            QueryNode::Phrase { words, .. } => words.iter().map(String::as_str).collect(),
+            QueryNode::Near { items, .. } | QueryNode::And(items) | QueryNode::Or(items) => {
+                items.iter().flat_map(QueryNode::words).collect()
+            }
+            QueryNode::Not(left, _) => left.words(),
+            QueryNode::Field { node, .. } => node.words(),
+        }
+    }
+}
+
+/// Parses `query` in the given syntax, allowing `field:` scopes for the names in
+/// `fields`. Returns `None` when the query contains no searchable words, and
+/// [`LocalSearchError::InvalidQuery`] for invalid advanced syntax.
+pub fn parse_query(query: &str, syntax: QuerySyntax, fields: &[&str]) -> Result<Option<QueryNode>> {
+    match syntax {
+        QuerySyntax::Plain => Ok(parse_plain(query, fields)),
+        QuerySyntax::Advanced => Parser::new(query, fields)?.parse(),
+    }
+}
+
+/// Parses `query` and renders it as an FTS5 match expression.
+pub fn to_fts_query(query: &str, syntax: QuerySyntax, fields: &[&str]) -> Result<Option<String>> {
+    Ok(parse_query(query, syntax, fields)?.map(|node| node.to_fts()))
+}
+
+fn parse_plain(query: &str, fields: &[&str]) -> Option<QueryNode> {
+    let terms: Vec<QueryNode> = query
+        .split_whitespace()
+        .filter_map(|word| {
+            let term = |text: &str| {
+                has_token_chars(text).then(|| QueryNode::Term {
+                    text: text.to_string(),
+                    prefix: false,
+                })
+            };
+            match split_field(word) {
+                Some((name, text)) if fields.contains(&name) && has_token_chars(text) => term(text)
+                    .map(|node| QueryNode::Field {
+                        name: name.to_string(),
+                        node: Box::new(node),
+                    }),
+                _ => term(word),
+            }
+        })
+        .collect();
+    combine(terms, QueryNode::And)
+}
+
+/// Splits `name:rest` where `name` looks like a field name (lowercase letters, digits
+/// and `_`, starting with a letter).
+fn split_field(word: &str) -> Option<(&str, &str)> {
+    let (name, rest) = word.split_once(':')?;
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    valid.then_some((name, rest))
+}
+
+/// Whether the FTS tokenizer would find at least one token in `word`. Words made only
+/// of separators such as `++` would otherwise become empty phrases.
+fn has_token_chars(word: &str) -> bool {
+    word.chars().any(|c| c.is_alphanumeric() || !c.is_ascii())
+}
+
+fn quote(text: &str, prefix: bool) -> String {
+    format!(
+        "\"{}\"{}",
+        text.replace('"', "\"\""),
+        if prefix { "*" } else { "" }
+    )
+}
+
+fn group(node: &QueryNode) -> String {
+    match node {
+        QueryNode::And(_) | QueryNode::Or(_) | QueryNode::Not(..) | QueryNode::Field { .. } => {
+            format!("({})", node.to_fts())
+        }
+        _ => node.to_fts(),
+    }
+}
+
+fn join_group(nodes: &[QueryNode], separator: &str) -> String {
+    nodes.iter().map(group).collect::<Vec<_>>().join(separator)
+}
+
+fn combine(mut nodes: Vec<QueryNode>, make: fn(Vec<QueryNode>) -> QueryNode) -> Option<QueryNode> {
+    match nodes.len() {
+        0 => None,
+        1 => nodes.pop(),
+        _ => Some(make(nodes)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Star,
+    Open,
+    Close,
+    Comma,
+    And,
+    Or,
+    Not,
+    Near,
+}
+
+fn describe(token: Option<&Token>) -> String {
+    match token {
+        None => "end of query".to_string(),
+        Some(Token::Word(word)) => format!("'{}'", word),
+        Some(Token::Phrase(phrase)) => format!("\"{}\"", phrase),
+        Some(Token::Star) => "'*'".to_string(),
+        Some(Token::Open) => "'('".to_string(),
+        Some(Token::Close) => "')'".to_string(),
+        Some(Token::Comma) => "','".to_string(),
+        Some(Token::And) => "AND".to_string(),
+        Some(Token::Or) => "OR".to_string(),
+        Some(Token::Not) => "NOT".to_string(),
+        Some(Token::Near) => "NEAR".to_string(),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> LocalSearchError {
+    LocalSearchError::InvalidQuery(message.into())
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            phrase.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err(invalid("Unterminated phrase: missing closing '\"'")),
+                    }
+                }
+                tokens.push(Token::Phrase(phrase));
+            }
+            '(' | ')' | ',' | '*' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    ',' => Token::Comma,
+                    _ => Token::Star,
+                });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '"' | '(' | ')' | ',' | '*') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    "NEAR" if chars.peek() == Some(&'(') => Token::Near,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser for the advanced syntax. `OR` binds loosest, then `AND`
+/// (explicit or implicit), then `NOT`.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    fields: &'a [&'a str],
+}
+
+impl<'a> Parser<'a> {
+    fn new(query: &str, fields: &'a [&'a str]) -> Result<Self> {
+        Ok(Parser {
+            tokens: tokenize(query)?,
+            position: 0,
+            fields,
+        })
+    }
+
+    fn parse(mut self) -> Result<Option<QueryNode>> {
+        if self.tokens.is_empty() {
+            return Ok(None);
+        }
+        let node = self.parse_or()?;
+        if self.position < self.tokens.len() {
+            return Err(invalid(format!(
+                "Unexpected {} at position {}",
+                describe(self.peek()),
+                self.position + 1
+            )));
+        }
+        Ok(node)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Option<QueryNode>> {
+        let mut nodes = Vec::new();
+        nodes.extend(self.parse_and()?);
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            nodes.extend(self.parse_and()?);
+        }
+        Ok(combine(nodes, QueryNode::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Option<QueryNode>> {
+        let mut nodes = Vec::new();
+        nodes.extend(self.parse_not()?);
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                    nodes.extend(self.parse_not()?);
+                }
+                Some(Token::Word(_) | Token::Phrase(_) | Token::Open | Token::Near) => {
+                    nodes.extend(self.parse_not()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(combine(nodes, QueryNode::And))
+    }
+
+    fn parse_not(&mut self) -> Result<Option<QueryNode>> {
+        let mut node = self.parse_primary()?;
+        while self.peek() == Some(&Token::Not) {
+            self.next();
+            let right = self.parse_primary()?;
+            node = match (node, right) {
+                (Some(left), Some(right)) => Some(QueryNode::Not(Box::new(left), Box::new(right))),
+                (left, _) => left,
+            };
+        }
+        Ok(node)
+    }
+
+    /// Parses one operand. Returns `None` for operands without searchable words, which
+    /// are dropped like in plain mode.
+    fn parse_primary(&mut self) -> Result<Option<QueryNode>> {
+        let position = self.position + 1;
+        match self.next() {
+            Some(Token::Word(word)) => match split_field(&word) {
+                Some((name, rest)) => self.parse_field(name, rest, position),
+                None => Ok(self.term(word)),
+            },
+            Some(Token::Phrase(phrase)) => Ok(self.phrase(&phrase)),
+            Some(Token::Open) => {
+                if self.peek() == Some(&Token::Close) {
+                    return Err(invalid(format!(
+                        "Empty parentheses at position {}",
+                        position
+                    )));
+                }
+                let node = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(node),
+                    other => Err(invalid(format!(
+                        "Missing ')' for '(' at position {}, found {}",
+                        position,
+                        describe(other.as_ref())
+                    ))),
+                }
+            }
+            Some(Token::Near) => self.parse_near(position).map(Some),
+            Some(Token::Not) if position == 1 => Err(invalid(
+                "NOT needs a term on its left, e.g. 'rust NOT java'",
+            )),
+            other => Err(invalid(format!(
+                "Expected a term at position {}, found {}",
+                position,
+                describe(other.as_ref())
+            ))),
+        }
+    }
+
+    /// Parses the operand of `name:`, which is the rest of the word or, if that is
+    /// empty, the following phrase, group or `NEAR`.
+    fn parse_field(
+        &mut self,
+        name: &str,
+        rest: &str,
+        position: usize,
+    ) -> Result<Option<QueryNode>> {
+        if !self.fields.contains(&name) {
+            return Err(invalid(format!(
+                "Unknown field '{}' at position {}; searchable fields are {}",
+                name,
+                position,
+                self.fields.join(", ")
+            )));
+        }
+        let node = if rest.is_empty() {
+            if matches!(
+                self.peek(),
+                None | Some(Token::Close | Token::Or | Token::And)
+            ) {
+                return Err(invalid(format!(
+                    "Expected a term after '{}:' at position {}",
+                    name, position
+                )));
+            }
+            self.parse_primary()?
+        } else {
+            self.term(rest.to_string())
+        };
+        Ok(node.map(|node| QueryNode::Field {
+            name: name.to_string(),
+            node: Box::new(node),
+        }))
+    }
+
+    fn prefix(&mut self) -> bool {
+        let prefix = self.peek() == Some(&Token::Star);
+        if prefix {
+            self.next();
+        }
+        prefix
+    }
+
+    fn term(&mut self, word: String) -> Option<QueryNode> {
+        let prefix = self.prefix();
+        has_token_chars(&word).then_some(QueryNode::Term { text: word, prefix })
+    }
+
+    fn phrase(&mut self, phrase: &str) -> Option<QueryNode> {
+        let prefix = self.prefix();
+        let words: Vec<String> = phrase
+            .split_whitespace()
+            .filter(|word| has_token_chars(word))
+            .map(str::to_string)
+            .collect();
+        (!words.is_empty()).then_some(QueryNode::Phrase { words, prefix })
+    }
+
+    fn parse_near(&mut self, position: usize) -> Result<QueryNode> {
+        self.next(); // '(' is guaranteed by the tokenizer
+        let mut items = Vec::new();
+        let mut distance = DEFAULT_NEAR_DISTANCE;
+        loop {
+            match self.next() {
+                Some(Token::Word(word)) => items.extend(self.term(word)),
+                Some(Token::Phrase(phrase)) => items.extend(self.phrase(&phrase)),
+                Some(Token::Comma) => {
+                    distance = match self.next() {
+                        Some(Token::Word(number)) => number
+                            .parse()
+                            .map_err(|_| invalid(format!("Invalid NEAR distance '{}'", number)))?,
+                        other => {
+                            return Err(invalid(format!(
+                                "Expected a NEAR distance, found {}",
+                                describe(other.as_ref())
+                            )));
+                        }
+                    };
+                    match self.next() {
+                        Some(Token::Close) => break,
+                        other => {
+                            return Err(invalid(format!(
+                                "Expected ')' after NEAR distance, found {}",
+                                describe(other.as_ref())
+                            )));
+                        }
+                    }
+                }
+                Some(Token::Close) => break,
+                other => {
+                    return Err(invalid(format!(
+                        "NEAR at position {} only accepts terms and phrases, found {}",
+                        position,
+                        describe(other.as_ref())
+                    )));
+                }
+            }
+        }
+        if items.len() < 2 {
+            return Err(invalid(format!(
+                "NEAR at position {} needs at least two terms",
+                position
+            )));
+        }
+        Ok(QueryNode::Near { items, distance })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advanced(query: &str) -> Result<Option<String>> {
+        to_fts_query(query, QuerySyntax::Advanced, &["content", "title"])
+    }
+
+    #[test]
+    fn test_plain_queries_are_quoted() {
+        let plain = |query| to_fts_query(query, QuerySyntax::Plain, &[]).unwrap();
+        assert_eq!(plain("C++ foo-bar").unwrap(), "\"C++\" AND \"foo-bar\"");
+        assert_eq!(
+            plain("\"unbalanced AND").unwrap(),
+            "\"\"\"unbalanced\" AND \"AND\""
+        );
+        assert_eq!(plain("rust").unwrap(), "\"rust\"");
+        assert_eq!(plain("  ++ -- "), None);
+    }
+
+    #[test]
+    fn test_advanced_operators() {
+        assert_eq!(
+            advanced("rust OR go NOT java").unwrap().unwrap(),
+            "\"rust\" OR (\"go\" NOT \"java\")"
+        );
+        assert_eq!(
+            advanced("(rust OR go) async").unwrap().unwrap(),
+            "(\"rust\" OR \"go\") AND \"async\""
+        );
+        assert_eq!(
+            advanced("\"error handling\" tok*").unwrap().unwrap(),
+            "\"error handling\" AND \"tok\"*"
+        );
+        assert_eq!(
+            advanced("NEAR(sqlite \"full text\", 5)").unwrap().unwrap(),
+            "NEAR(\"sqlite\" \"full text\", 5)"
+        );
+        assert_eq!(
+            advanced("near and or").unwrap().unwrap(),
+            "\"near\" AND \"and\" AND \"or\""
+        );
+    }
+
+    #[test]
+    fn test_advanced_syntax_errors() {
+        for query in [
+            "\"unbalanced",
+            "(rust",
+            "rust)",
+            "()",
+            "AND",
+            "rust OR",
+            "NOT rust",
+            "NEAR(rust)",
+            "NEAR(rust go, x)",
+            "author:smith",
+            "title:",
+        ] {
+            assert!(
+                matches!(advanced(query), Err(LocalSearchError::InvalidQuery(_))),
+                "{} should be rejected",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_field_scopes() {
+        let plain = |query| to_fts_query(query, QuerySyntax::Plain, &["title"]).unwrap();
+        assert_eq!(
+            plain("title:rust guide").unwrap(),
+            "(title : \"rust\") AND \"guide\""
+        );
+        assert_eq!(plain("author:smith").unwrap(), "\"author:smith\"");
+        assert_eq!(plain("title:").unwrap(), "\"title:\"");
+
+        assert_eq!(
+            advanced("title:\"error handling\" NOT title:(java OR go)")
+                .unwrap()
+                .unwrap(),
+            "(title : \"error handling\") NOT (title : (\"java\" OR \"go\"))"
+        );
+        assert_eq!(
+            advanced("title:tok* 12:30").unwrap().unwrap(),
+            "(title : \"tok\"*) AND \"12:30\""
+        );
+    }
+}
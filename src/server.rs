@@ -0,0 +1,516 @@
+//! Local HTTP server exposing search and document management as a JSON API.
+//!
+//! The server keeps one engine (and its embedding model) loaded for its whole
+//! lifetime, so queries don't pay the model start-up cost. Requests are handled one
+//! at a time on the thread calling [`SearchServer::run`].
+//!
+//! | Method   | Path                 | Body / query                         | Response            |
+//! |----------|----------------------|--------------------------------------|---------------------|
+//! | `GET`    | `/health`            |                                      | `{"status": "ok"}`  |
+//! | `POST`   | `/search`            | [`SearchRequest`]                    | [`SearchResponse`]  |
+//! | `GET`    | `/search`            | `?q=...&type=...&limit=...&path_filter=a,b&collection=c,d&syntax=advanced&fuzzy=true&expand=true&explain=true&mmr_lambda=0.7` | [`SearchResponse`] |
+//! | `GET`    | `/similar`           | `?path=...&limit=...&terms=...&path_filter=a,b&collection=c,d` | `{"path", "results_count", "results"}` |
+//! | `POST`   | `/documents`         | a [`DocumentRequest`] or an array    | `{"upserted": n}`   |
+//! | `GET`    | `/documents`         | `?path=...`                          | [`StoredDocument`]  |
+//! | `DELETE` | `/documents`         | `?path=...`                          | `{"deleted": path}` |
+//! | `GET`    | `/stats`             |                                      | [`IndexStats`](crate::IndexStats) |
+//!
+//! Errors are returned as `{"error": "message"}` with a 4xx/5xx status.
+
+use crate::{
+    DocumentIndexer, DocumentRequest, LocalSearch, LocalSearchError, QueryExpansion, QuerySyntax,
+    SearchOptions, SearchResult, SearchType, StoredDocument,
+};
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Body of `POST /search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    /// `fulltext`, `semantic` or `hybrid` (default).
+    #[serde(default)]
+    pub search_type: Option<String>,
+    /// Maximum number of results (default 10).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Only return documents whose path contains one of these patterns.
+    #[serde(default)]
+    pub path_filters: Option<Vec<String>>,
+    /// Only return documents whose metadata has all of these key/value pairs.
+    #[serde(default)]
+    pub metadata_filters: Option<HashMap<String, String>>,
+    /// Collections to search; `["*"]` searches all of them.
+    #[serde(default)]
+    pub collections: Option<Vec<String>>,
+    /// `plain` (default) or `advanced` query syntax.
+    #[serde(default)]
+    pub query_syntax: QuerySyntax,
+    /// Tolerate typos in query words missing from the index.
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// Add terms from the top semantic matches to the full-text query.
+    #[serde(default)]
+    pub query_expansion: Option<QueryExpansion>,
+    /// Attach a score breakdown to every result.
+    #[serde(default)]
+    pub explain: bool,
+    /// Diversify results by maximal marginal relevance, between 0 and 1.
+    #[serde(default)]
+    pub mmr_lambda: Option<f64>,
+}
+
+/// Response of `/search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub search_type: SearchType,
+    pub results_count: usize,
+    pub results: Vec<SearchResult>,
+    /// Corrected query if some words do not occur in the index ("did you mean").
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+/// Body of `POST /documents`: one document or a list of documents.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum UpsertBody {
+    One(DocumentRequest),
+    Many(Vec<DocumentRequest>),
+}
+
+/// Error returned to HTTP clients with a status code.
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        HttpError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for HttpError {
+    fn from(error: anyhow::Error) -> Self {
+        HttpError::new(500, error.to_string())
+    }
+}
+
+impl From<LocalSearchError> for HttpError {
+    fn from(error: LocalSearchError) -> Self {
+        // A missing embedder is a server configuration problem, so it stays a 500
+        let status = match error {
+            LocalSearchError::InvalidQuery(_)
+            | LocalSearchError::InvalidArgument(_)
+            | LocalSearchError::DimensionMismatch { .. } => 400,
+            LocalSearchError::NotFound(_) => 404,
+            _ => 500,
+        };
+        HttpError::new(status, error.to_string())
+    }
+}
+
+type HttpResult = Result<(u16, serde_json::Value), HttpError>;
+
+/// HTTP server serving search and document requests from a single engine.
+pub struct SearchServer<E> {
+    engine: E,
+    server: tiny_http::Server,
+}
+
+impl<E: DocumentIndexer + LocalSearch> SearchServer<E> {
+    /// Binds the server to `addr` (e.g. `127.0.0.1:8080`; port 0 picks a free port).
+    pub fn bind(engine: E, addr: &str) -> anyhow::Result<Self> {
+        let server = tiny_http::Server::http(addr)
+            .map_err(|e| anyhow!("Failed to bind server to {}: {}", addr, e))?;
+        info!("Search server listening on {}", server.server_addr());
+        Ok(SearchServer { engine, server })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Serves requests until the listener is closed.
+    pub fn run(&self) {
+        for mut request in self.server.incoming_requests() {
+            let (status, body) = match self.handle(&mut request) {
+                Ok(response) => response,
+                Err(e) => (e.status, serde_json::json!({ "error": e.message })),
+            };
+            debug!("{} {} -> {}", request.method(), request.url(), status);
+
+            let response = tiny_http::Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(
+                    tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                        .expect("static header is valid"),
+                );
+            if let Err(e) = request.respond(response) {
+                warn!("Failed to send response: {}", e);
+            }
+        }
+    }
+
+    fn handle(&self, request: &mut tiny_http::Request) -> HttpResult {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+        match (method, path) {
+            (tiny_http::Method::Get, "/health") => Ok((200, serde_json::json!({ "status": "ok" }))),
+            (tiny_http::Method::Post, "/search") => {
+                let search_request: SearchRequest = read_json(request)?;
+                self.search(search_request)
+            }
+            (tiny_http::Method::Get, "/search") => {
+                let search_request = SearchRequest {
+                    query: query_param(query, "q")
+                        .ok_or_else(|| HttpError::new(400, "Missing query parameter 'q'"))?,
+                    search_type: query_param(query, "type"),
+                    limit: query_param(query, "limit")
+                        .map(|limit| limit.parse())
+                        .transpose()
+                        .map_err(|_| HttpError::new(400, "Invalid 'limit' parameter"))?,
+                    path_filters: query_param(query, "path_filter")
+                        .map(|filters| split_list(&filters)),
+                    metadata_filters: None,
+                    collections: query_param(query, "collection").map(|names| split_list(&names)),
+                    query_syntax: query_param(query, "syntax")
+                        .map(|syntax| syntax.parse())
+                        .transpose()?
+                        .unwrap_or_default(),
+                    fuzzy: query_param(query, "fuzzy").is_some_and(|fuzzy| fuzzy == "true"),
+                    query_expansion: query_param(query, "expand")
+                        .is_some_and(|expand| expand == "true")
+                        .then(QueryExpansion::default),
+                    explain: query_param(query, "explain").is_some_and(|explain| explain == "true"),
+                    mmr_lambda: query_param(query, "mmr_lambda")
+                        .map(|lambda| lambda.parse())
+                        .transpose()
+                        .map_err(|_| HttpError::new(400, "Invalid 'mmr_lambda' parameter"))?,
+                };
+                self.search(search_request)
+            }
+            (tiny_http::Method::Get, "/similar") => {
+                let path = required_path(query)?;
+                let parse_number = |name: &str| {
+                    query_param(query, name)
+                        .map(|value| value.parse::<usize>())
+                        .transpose()
+                        .map_err(|_| HttpError::new(400, format!("Invalid '{}' parameter", name)))
+                };
+                let options = SearchOptions {
+                    limit: parse_number("limit")?,
+                    path_filters: query_param(query, "path_filter")
+                        .map(|filters| split_list(&filters)),
+                    collections: query_param(query, "collection").map(|names| split_list(&names)),
+                    ..SearchOptions::default()
+                };
+                let terms = parse_number("terms")?.unwrap_or_default();
+                let results = self.engine.find_similar(&path, terms, &options)?;
+                Ok((
+                    200,
+                    serde_json::json!({
+                        "path": path,
+                        "results_count": results.len(),
+                        "results": results,
+                    }),
+                ))
+            }
+            (tiny_http::Method::Post, "/documents") | (tiny_http::Method::Put, "/documents") => {
+                let requests = match read_json(request)? {
+                    UpsertBody::One(document) => vec![document],
+                    UpsertBody::Many(documents) => documents,
+                };
+                let count = requests.len();
+                self.engine.upsert_batch(requests, None)?;
+                Ok((200, serde_json::json!({ "upserted": count })))
+            }
+            (tiny_http::Method::Get, "/documents") => {
+                let path = required_path(query)?;
+                let document = self.find_document(&path)?;
+                Ok((
+                    200,
+                    serde_json::to_value(document).map_err(anyhow::Error::from)?,
+                ))
+            }
+            (tiny_http::Method::Delete, "/documents") => {
+                let path = required_path(query)?;
+                self.find_document(&path)?;
+                self.engine.delete_document(&path)?;
+                Ok((200, serde_json::json!({ "deleted": path })))
+            }
+            (tiny_http::Method::Get, "/stats") => {
+                let stats = self.engine.stats()?;
+                Ok((
+                    200,
+                    serde_json::to_value(stats).map_err(anyhow::Error::from)?,
+                ))
+            }
+            (_, "/health" | "/search" | "/similar" | "/documents" | "/stats") => {
+                Err(HttpError::new(405, "Method not allowed"))
+            }
+            _ => Err(HttpError::new(404, format!("Unknown endpoint: {}", path))),
+        }
+    }
+
+    fn search(&self, request: SearchRequest) -> HttpResult {
+        let search_type: SearchType = request.search_type.as_deref().unwrap_or("hybrid").parse()?;
+        let options = SearchOptions {
+            limit: request.limit,
+            path_filters: request.path_filters,
+            metadata_filters: request.metadata_filters,
+            collections: request.collections,
+            query_syntax: request.query_syntax,
+            fuzzy: request.fuzzy,
+            query_expansion: request.query_expansion,
+            explain: request.explain,
+            mmr_lambda: request.mmr_lambda,
+        };
+        let outcome =
+            self.engine
+                .search_with_details(&request.query, search_type.clone(), &options)?;
+        let response = SearchResponse {
+            query: request.query,
+            search_type,
+            results_count: outcome.results.len(),
+            results: outcome.results,
+            suggestion: outcome.suggestion,
+        };
+        Ok((
+            200,
+            serde_json::to_value(response).map_err(anyhow::Error::from)?,
+        ))
+    }
+
+    fn find_document(&self, path: &str) -> Result<StoredDocument, HttpError> {
+        self.engine
+            .get_document(path)?
+            .ok_or_else(|| HttpError::new(404, format!("Document not found: {}", path)))
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(
+    request: &mut tiny_http::Request,
+) -> Result<T, HttpError> {
+    serde_json::from_reader(request.as_reader())
+        .map_err(|e| HttpError::new(400, format!("Invalid request body: {}", e)))
+}
+
+fn required_path(query: &str) -> Result<String, HttpError> {
+    query_param(query, "path").ok_or_else(|| HttpError::new(400, "Missing query parameter 'path'"))
+}
+
+/// Splits a comma-separated query parameter, dropping empty entries.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Returns the percent-decoded value of `name` in a URL query string.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(key, _)| percent_decode(key) == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> String {
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|d| d as u8);
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(high), Some(low)) => {
+                    decoded.push(high << 4 | low);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SqliteLocalSearchEngine;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use tempfile::TempDir;
+
+    /// Starts a server on a free localhost port and returns its address.
+    fn start_server(temp_dir: &TempDir) -> SocketAddr {
+        let db_path = temp_dir.path().join("test.db");
+        let engine = SqliteLocalSearchEngine::new(db_path.to_str().unwrap(), None).unwrap();
+        engine.create_table().unwrap();
+        let server = SearchServer::bind(engine, "127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        addr
+    }
+
+    /// Sends a request and returns the status code and parsed JSON body.
+    fn send(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let body = body.unwrap_or("");
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_document_endpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let addr = start_server(&temp_dir);
+
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/documents",
+            Some(
+                r#"[{"path": "notes/rust.md", "content": "Rust ownership rules", "metadata": {"tag": "rust"}},
+                    {"path": "notes/python.md", "content": "Python generators", "metadata": null}]"#,
+            ),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["upserted"], 2);
+
+        let (status, body) = send(addr, "GET", "/documents?path=notes%2Frust.md", None);
+        assert_eq!(status, 200);
+        assert_eq!(body["content"], "Rust ownership rules");
+        assert_eq!(body["metadata"]["tag"], "rust");
+
+        let (status, body) = send(addr, "GET", "/stats", None);
+        assert_eq!(status, 200);
+        assert_eq!(body["document_count"], 2);
+
+        let (status, _) = send(addr, "DELETE", "/documents?path=notes/python.md", None);
+        assert_eq!(status, 200);
+        let (status, body) = send(addr, "GET", "/documents?path=notes/python.md", None);
+        assert_eq!(status, 404);
+        assert!(body["error"].as_str().unwrap().contains("notes/python.md"));
+        let (_, body) = send(addr, "GET", "/stats", None);
+        assert_eq!(body["document_count"], 1);
+    }
+
+    #[test]
+    fn test_search_endpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let addr = start_server(&temp_dir);
+        send(
+            addr,
+            "POST",
+            "/documents",
+            Some(r#"{"path": "a.md", "content": "local search engine in rust", "metadata": null}"#),
+        );
+        send(
+            addr,
+            "POST",
+            "/documents",
+            Some(r#"{"path": "b.md", "content": "cooking recipes", "metadata": null}"#),
+        );
+
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/search",
+            Some(
+                r#"{"query": "rust", "search_type": "fulltext", "limit": 5, "metadata_filters": {}}"#,
+            ),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["results_count"], 1);
+        assert_eq!(body["results"][0]["path"], "a.md");
+        assert!(body["results"][0]["fts_score"].as_f64().unwrap() > 0.0);
+
+        let (status, body) = send(addr, "GET", "/search?q=cooking+recipes&type=fts", None);
+        assert_eq!(status, 200);
+        assert_eq!(body["results"][0]["path"], "b.md");
+
+        let (status, body) = send(addr, "GET", "/search?q=cookng&type=fts&fuzzy=true", None);
+        assert_eq!(status, 200);
+        assert_eq!(body["results"][0]["path"], "b.md");
+        assert_eq!(body["suggestion"], "cooking");
+
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/search",
+            Some(r#"{"query": "rust", "search_type": "semantic"}"#),
+        );
+        assert_eq!(status, 500);
+        assert!(body["error"].as_str().unwrap().contains("embedder"));
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let temp_dir = TempDir::new().unwrap();
+        let addr = start_server(&temp_dir);
+
+        let (status, _) = send(addr, "POST", "/search", Some("not json"));
+        assert_eq!(status, 400);
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/search",
+            Some(r#"{"query": "x", "search_type": "magic"}"#),
+        );
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("magic"));
+        let (status, _) = send(addr, "GET", "/documents", None);
+        assert_eq!(status, 400);
+        let (status, _) = send(addr, "DELETE", "/stats", None);
+        assert_eq!(status, 405);
+        let (status, _) = send(addr, "GET", "/nope", None);
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_query_param_decoding() {
+        assert_eq!(
+            query_param("q=a+b%2Fc&type=fts", "q").as_deref(),
+            Some("a b/c")
+        );
+        assert_eq!(query_param("q=%zz&x", "x").as_deref(), Some(""));
+        assert_eq!(query_param("q=%zz", "q").as_deref(), Some("%zz"));
+        assert_eq!(query_param("q=1", "path"), None);
+    }
+}
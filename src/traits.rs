@@ -1,3 +1,6 @@
+use crate::LocalEmbedder;
+use crate::error::{LocalSearchError, Result};
+use crate::query::QuerySyntax;
 use serde::{Deserialize, Serialize};
 
 /// Search strategy for querying documents.
@@ -8,16 +11,70 @@ pub enum SearchType {
     Hybrid,
 }
 
+impl std::str::FromStr for SearchType {
+    type Err = LocalSearchError;
+
+    /// Parses the names used by the CLI and server: `fulltext`/`fts`,
+    /// `semantic`/`embedding` and `hybrid`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "fulltext" | "fts" => Ok(SearchType::FullText),
+            "semantic" | "embedding" => Ok(SearchType::Semantic),
+            "hybrid" => Ok(SearchType::Hybrid),
+            _ => Err(LocalSearchError::InvalidArgument(format!(
+                "Unknown search type '{}'. Use 'fulltext', 'semantic' or 'hybrid'.",
+                s
+            ))),
+        }
+    }
+}
+
 /// Result from a search operation with scores and metadata.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub path: String,
+    /// Collection the document belongs to.
+    #[serde(default)]
+    pub collection: String,
     pub metadata: Option<std::collections::HashMap<String, String>>,
     pub created_at: f64,
     pub updated_at: f64,
     pub fts_score: Option<f64>,
     pub semantic_score: Option<f64>,
     pub final_score: f64,
+    /// How the scores were computed, with [`SearchOptions::explain`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
+}
+
+/// Breakdown of a result's scores, from the raw ranking values to `final_score`.
+///
+/// Full-text scores are the negated BM25 values, normalized with a softmax over the
+/// matches; hybrid search divides them by the best one and blends them with the
+/// cosine similarity: `final_score = fts_weight * fts_normalized + semantic_weight *
+/// cosine_similarity`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreExplanation {
+    /// Raw `bm25()` value of the full-text match; lower is better.
+    pub bm25: Option<f64>,
+    /// Full-text score after the softmax.
+    pub fts_softmax: Option<f64>,
+    /// Full-text score after dividing by the best one, as blended by hybrid search.
+    pub fts_normalized: Option<f64>,
+    pub cosine_similarity: Option<f64>,
+    /// Weight of the full-text score in `final_score`.
+    pub fts_weight: f64,
+    /// Weight of the cosine similarity in `final_score`.
+    pub semantic_weight: f64,
+    /// Position in the full-text matches, starting at 1.
+    pub fts_rank: Option<usize>,
+    /// Position in the semantic matches, starting at 1.
+    pub semantic_rank: Option<usize>,
+    /// Filters the document passed, e.g. `path contains "src"`.
+    pub filters: Vec<String>,
+    /// Highest cosine similarity to the results ranked above it, when re-ranked by
+    /// [`SearchOptions::mmr_lambda`].
+    pub redundancy: Option<f64>,
 }
 
 /// Request to index a document with content and metadata.
@@ -26,15 +83,192 @@ pub struct DocumentRequest {
     pub path: String,
     pub content: String,
     pub metadata: Option<std::collections::HashMap<String, String>>,
+    /// Precomputed embedding of the content, stored instead of embedding it with the
+    /// engine's embedder. It must not be empty and must have the dimension of the
+    /// index's other embeddings, or of the embedder's if none are stored yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Options for [`LocalSearch::search_with_options`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Maximum number of results (default 10).
+    pub limit: Option<usize>,
+    /// Only match documents whose path contains any of these patterns.
+    pub path_filters: Option<Vec<String>>,
+    /// Only match documents whose metadata has all of these key/value pairs.
+    pub metadata_filters: Option<std::collections::HashMap<String, String>>,
+    /// Collections to search instead of the engine's own; `"*"` searches all of them.
+    #[serde(default)]
+    pub collections: Option<Vec<String>>,
+    /// How the query is interpreted by full-text search (plain words by default).
+    #[serde(default)]
+    pub query_syntax: QuerySyntax,
+    /// Also match index terms within a few typos of query words missing from the index.
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// Add terms from the best semantic matches to full-text queries (needs an embedder).
+    #[serde(default)]
+    pub query_expansion: Option<QueryExpansion>,
+    /// Attach a [`ScoreExplanation`] to every result.
+    #[serde(default)]
+    pub explain: bool,
+    /// Re-rank the best candidates by maximal marginal relevance to avoid
+    /// near-duplicates: 1 ranks by score alone, lower values favor results whose
+    /// stored embeddings differ from those ranked above them. Must be in `0..=1`.
+    #[serde(default)]
+    pub mmr_lambda: Option<f64>,
+}
+
+/// Pseudo-relevance feedback: the documents closest to the query by embedding are
+/// assumed relevant, and their most distinctive words are added to the full-text
+/// query as alternatives, so documents using other vocabulary can still match.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QueryExpansion {
+    /// Number of top semantic matches to take terms from.
+    pub documents: usize,
+    /// Number of terms added to the query.
+    pub terms: usize,
+}
+
+impl Default for QueryExpansion {
+    fn default() -> Self {
+        QueryExpansion {
+            documents: 3,
+            terms: 5,
+        }
+    }
+}
+
+/// How a query was rewritten for full-text search in one collection, as returned by
+/// [`LocalSearch::explain_query`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryExplanation {
+    pub collection: String,
+    /// FTS5 match expression that is run, or `None` if the query has no searchable
+    /// words.
+    pub fts_query: Option<String>,
+    /// Query terms and the synonyms they also match.
+    pub synonyms: std::collections::BTreeMap<String, Vec<String>>,
+    /// Misspelt query words and the index words they also match.
+    pub corrections: std::collections::BTreeMap<String, Vec<String>>,
+    /// Terms added by [`SearchOptions::query_expansion`].
+    pub expansion_terms: Vec<String>,
+}
+
+/// Results of [`LocalSearch::search_with_details`] with what was learnt about the
+/// query while rewriting it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchResult>,
+    /// Corrected query if some words do not occur in the index ("did you mean").
+    pub suggestion: Option<String>,
+    /// How the query was rewritten in each collection, with [`SearchOptions::explain`].
+    pub query_explanations: Vec<QueryExplanation>,
+}
+
+/// A document as stored in the index, with its content and timestamps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredDocument {
+    pub path: String,
+    pub content: String,
+    pub metadata: Option<std::collections::HashMap<String, String>>,
+    pub created_at: f64,
+    pub updated_at: f64,
+}
+
+/// Summary of a stored document returned when listing documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    pub path: String,
+    pub metadata: Option<std::collections::HashMap<String, String>>,
+    pub created_at: f64,
+    pub updated_at: f64,
+}
+
+/// Options for [`DocumentIndexer::list_documents`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListOptions {
+    /// Only list documents whose path starts with this prefix.
+    pub path_prefix: Option<String>,
+    /// Only list documents whose metadata has all of these key/value pairs.
+    pub metadata_filters: Option<std::collections::HashMap<String, String>>,
+    /// Maximum number of documents to return (all if `None`).
+    pub limit: Option<usize>,
+    /// Number of documents to skip, for pagination.
+    pub offset: usize,
+}
+
+/// Health and size statistics of an index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub document_count: i64,
+    pub embedding_count: i64,
+    /// Embedded chunks. Documents are currently embedded whole, so this equals
+    /// `embedding_count`.
+    pub chunk_count: i64,
+    pub documents_missing_embeddings: i64,
+    /// Rows in the full-text index; equals `document_count` in a healthy index.
+    pub fts_row_count: i64,
+    /// Whether the full-text index passed its integrity check.
+    pub fts_integrity_ok: bool,
+    /// Total size of the stored document content in bytes.
+    pub total_content_bytes: i64,
+    /// Dimension of the stored embeddings, if any.
+    pub embedding_dimension: Option<usize>,
+    /// Identifier of the model that produced the embeddings, if recorded.
+    pub embedding_model: Option<String>,
+    pub db_size_bytes: u64,
+    /// Unix timestamp (seconds) of the most recent document write.
+    pub last_updated_at: Option<f64>,
+}
+
+impl IndexStats {
+    /// Returns true if the full-text index matches the documents and embeddings are
+    /// either absent or present for every document. A false value usually means an
+    /// interrupted or partial ingestion.
+    pub fn is_healthy(&self) -> bool {
+        self.fts_integrity_ok
+            && self.fts_row_count == self.document_count
+            && (self.embedding_count == 0 || self.documents_missing_embeddings == 0)
+    }
 }
 
 /// Trait for managing documents in a search index.
 pub trait DocumentIndexer {
-    fn insert_document(&self, request: DocumentRequest) -> anyhow::Result<()>;
-    fn upsert_document(&self, request: DocumentRequest) -> anyhow::Result<()>;
-    fn delete_document(&self, path: &str) -> anyhow::Result<()>;
-    fn stats(&self) -> anyhow::Result<i64>;
-    fn refresh(&mut self) -> anyhow::Result<()>;
+    fn insert_document(&self, request: DocumentRequest) -> Result<()>;
+    fn upsert_document(&self, request: DocumentRequest) -> Result<()>;
+    fn delete_document(&self, path: &str) -> Result<()>;
+    fn get_document(&self, path: &str) -> Result<Option<StoredDocument>>;
+    /// Lists documents ordered by path.
+    fn list_documents(&self, options: &ListOptions) -> Result<Vec<DocumentInfo>>;
+    /// Deletes all documents whose path starts with `prefix` and returns how many.
+    fn delete_prefix(&self, prefix: &str) -> Result<usize>;
+    fn stats(&self) -> Result<IndexStats>;
+    fn refresh(&mut self) -> Result<()>;
+
+    /// Returns the embedder used for documents, if any, so callers can compute
+    /// embeddings ahead of writing them with [`DocumentIndexer::upsert_batch`].
+    fn embedder(&self) -> Option<&LocalEmbedder> {
+        None
+    }
+
+    /// Upserts a batch of documents in one write. When `embeddings` is provided it
+    /// holds one precomputed embedding per request, stored instead of re-embedding
+    /// unless the request carries its own [`DocumentRequest::embedding`]. The default
+    /// implementation ignores `embeddings`.
+    fn upsert_batch(
+        &self,
+        requests: Vec<DocumentRequest>,
+        embeddings: Option<Vec<Vec<f32>>>,
+    ) -> Result<()> {
+        let _ = embeddings;
+        for request in requests {
+            self.upsert_document(request)?;
+        }
+        Ok(())
+    }
 }
 
 /// Trait for performing searches on indexed documents.
@@ -45,5 +279,73 @@ pub trait LocalSearch {
         search_type: SearchType,
         top: Option<i8>,
         path_filters: Option<&[String]>,
-    ) -> anyhow::Result<Vec<SearchResult>>;
+    ) -> Result<Vec<SearchResult>>;
+
+    fn search_with_options(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Searches like [`search_with_options`](Self::search_with_options) and also returns
+    /// the [`suggest`](Self::suggest)ion and, with [`SearchOptions::explain`], the
+    /// [`explain_query`](Self::explain_query) output, for full-text and hybrid searches.
+    /// The default implementation calls those methods, which rewrites and embeds the
+    /// query again for each; engines should return all three from one rewrite.
+    fn search_with_details(
+        &self,
+        query: &str,
+        search_type: SearchType,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        let (suggestion, query_explanations) = match search_type {
+            SearchType::Semantic => (None, Vec::new()),
+            _ if options.explain => (
+                self.suggest(query, options)?,
+                self.explain_query(query, options)?,
+            ),
+            _ => (self.suggest(query, options)?, Vec::new()),
+        };
+        Ok(SearchOutcome {
+            results: self.search_with_options(query, search_type, options)?,
+            suggestion,
+            query_explanations,
+        })
+    }
+
+    /// Ranks documents by cosine similarity to an embedding computed elsewhere. It must
+    /// come from the model that embedded the index, with the same dimension.
+    /// `options` supply the limit, filters and collections.
+    fn search_by_vector(
+        &self,
+        embedding: &[f32],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Finds documents similar to the stored document at `path`, which is excluded,
+    /// by its stored embedding. With `terms` above zero its most distinctive words
+    /// are also matched by full-text search. `options` supply the limit, filters and
+    /// collections; the query settings are not used.
+    fn find_similar(
+        &self,
+        path: &str,
+        terms: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Returns a "did you mean" correction of `query` if some of its words do not occur
+    /// in the index but similar words do. The default implementation never suggests.
+    fn suggest(&self, query: &str, options: &SearchOptions) -> Result<Option<String>> {
+        let _ = (query, options);
+        Ok(None)
+    }
+
+    /// Shows how `query` is rewritten for full-text search in each searched collection:
+    /// synonyms, spelling corrections and expansion terms. The default implementation
+    /// explains nothing.
+    fn explain_query(&self, query: &str, options: &SearchOptions) -> Result<Vec<QueryExplanation>> {
+        let _ = (query, options);
+        Ok(Vec::new())
+    }
 }